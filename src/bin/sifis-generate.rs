@@ -1,22 +1,29 @@
-use std::path::PathBuf;
+use std::fs::{create_dir_all, read_to_string, write, File};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
-use anyhow::anyhow;
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Context};
+use clap::builder::PossibleValuesParser;
 use clap::parser::ValueSource;
-use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser};
+use clap::{Arg, ArgMatches, Args, Command, CommandFactory, FromArgMatches, Parser};
+use dialoguer::{Confirm, Input, Select};
 use figment::providers::{Format, Serialized, Toml};
+use figment::value::magic::RelativePathBuf;
 use figment::value::{Dict, Map, Value};
 use figment::{Figment, Profile};
 use figment::{Metadata, Provider};
 use serde::{Deserialize, Serialize};
 
-use sifis_generate::{CreateCi, CreateProject};
-
-use sifis_generate::cargo::Cargo;
-use sifis_generate::maven::Maven;
-use sifis_generate::meson::{Meson, ProjectKind};
-use sifis_generate::poetry::Poetry;
-use sifis_generate::yarn::Yarn;
+use sifis_generate::{
+    check, git_commit, git_identity, git_init, migrate_dep5, upgrade, Annotation, BuiltinTemplate,
+    ConflictPolicy, CustomLicense, Error, GenerationRequest, GitAuthor, InputKind, Licensing,
+    MergeStyle, Mode, Options, Plan, Registry, ReuseFormat, TarGzSink, Toolchain, ToolchainInput,
+    ZipSink,
+};
 
+use tracing::{info, warn};
 use tracing_subscriber::EnvFilter;
 
 #[derive(Parser, Debug)]
@@ -27,20 +34,65 @@ struct Opts {
     /// Output the generated paths as they are produced
     #[clap(short, long, global = true)]
     verbose: bool,
+    /// Print the generation plan instead of writing any file
+    #[clap(long, global = true)]
+    dry_run: bool,
+    /// Write the generated files into a `.tar.gz` or `.zip` archive
+    #[clap(long, global = true, value_hint = clap::ValueHint::FilePath)]
+    output_archive: Option<PathBuf>,
+    /// Use the templates in this directory instead of the built-in ones,
+    /// before the ones located in ${XDG_CONFIG_HOME}/sifis-generate/templates
+    #[clap(long, global = true, value_hint = clap::ValueHint::DirPath)]
+    templates_dir: Option<PathBuf>,
+    /// Set a variable available to the templates, it can be repeated
+    #[clap(long = "set", global = true, value_name = "KEY=VALUE", value_parser = key_value)]
+    vars: Vec<(String, String)>,
+    /// Ask for every input, showing the current values as defaults.
+    /// The missing inputs are asked anyway when running in a terminal
+    #[clap(short, long, global = true)]
+    interactive: bool,
+    /// Do not run the post-generation hooks
+    #[clap(long, global = true)]
+    no_hooks: bool,
+    /// Initialise a git repository in the project, on the project branch
+    #[clap(long, global = true)]
+    git_init: bool,
+    /// Commit the generated files in a new git repository
+    #[clap(long, global = true)]
+    git_commit: bool,
+    /// Author of the initial commit, instead of the one configured in git
+    #[clap(long, global = true, value_name = "NAME <EMAIL>")]
+    git_author: Option<GitAuthor>,
     #[clap(subcommand)]
     cmd: Cmd,
 }
 
-fn from_id(id: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync + 'static>> {
-    id.parse::<&dyn license::License>()
-        .map(|_| id.to_owned())
-        .map_err(|_| "License not found".into())
+fn key_value(
+    s: &str,
+) -> Result<(String, String), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("{s} is not in the KEY=VALUE form"))?;
+    Ok((key.to_owned(), value.to_owned()))
+}
+
+fn from_expression(
+    expression: &str,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    match Licensing::parse(expression, &[]) {
+        Ok(licensing) => Ok(licensing.expression().to_owned()),
+        // The custom licenses are known only once the configuration is read
+        Err(Error::UnknownLicense { license }) if license.starts_with("LicenseRef-") => {
+            Ok(expression.trim().to_owned())
+        }
+        Err(e) => Err(e.to_string().into()),
+    }
 }
 
 #[derive(Parser, Debug, Serialize, Deserialize)]
 struct CommonData {
-    /// License to be used in the project
-    #[clap(long, short, value_parser = from_id, default_value = "MIT")]
+    /// SPDX license expression of the project, such as `MIT OR Apache-2.0`
+    #[clap(long, short, value_parser = from_expression, default_value = "MIT")]
     license: String,
     /// GitHub branch name to be used in the project
     #[clap(long, short = 'b', default_value = "main")]
@@ -48,9 +100,44 @@ struct CommonData {
     /// Override the project name
     #[clap(long, default_value = "")]
     name: String,
+    /// Author of the project, the git `user.name` by default
+    #[clap(long, default_value = "")]
+    author: String,
+    /// Email of the author, the git `user.email` by default
+    #[clap(long, default_value = "")]
+    email: String,
+    /// Holder of the copyright, the author by default
+    #[clap(long, default_value = "")]
+    copyright_holder: String,
+    /// Year of the copyright notices, the current one by default
+    #[clap(long, default_value = "")]
+    year: String,
+    /// Format of the REUSE metadata: toml, or dep5 for the older tools
+    #[clap(long, value_parser = reuse_format, default_value = "toml")]
+    reuse_format: ReuseFormat,
+    /// What to do with the files which already exist: abort, skip, overwrite or backup
+    #[clap(long, value_parser = conflict_policy, default_value = "abort")]
+    on_conflict: ConflictPolicy,
+    /// Generate only these components, e.g. `ci,readme`
+    #[clap(long, value_delimiter = ',')]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    only: Vec<String>,
+    /// Do not generate these components, e.g. `docker,fuzz`
+    #[clap(long, value_delimiter = ',')]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    without: Vec<String>,
     /// Path to the new project
     #[clap(value_hint = clap::ValueHint::DirPath)]
-    project_path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project_path: Option<PathBuf>,
+}
+
+impl CommonData {
+    fn select_components(&self, options: &mut Options) {
+        options.only = self.only.clone();
+        options.without = self.without.clone();
+        options.reuse_format = self.reuse_format;
+    }
 }
 
 static DEFAULT_CONF: &str = r#"
@@ -58,9 +145,12 @@ static DEFAULT_CONF: &str = r#"
     license = "MIT"
     branch = "main"
     name = ""
-
-    [meson]
-    kind = "c"
+    author = ""
+    email = ""
+    copyright_holder = ""
+    year = ""
+    reuse_format = "toml"
+    on_conflict = "abort"
 "#;
 
 struct ClapSerialized<T> {
@@ -119,60 +209,535 @@ impl<T: Serialize> Provider for ClapSerialized<T> {
     }
 }
 
-#[derive(Parser, Debug, Serialize, Deserialize)]
-struct CargoData {
-    /// Docker image description.
+#[derive(Parser, Debug)]
+struct UpgradeData {
+    /// Write the conflicting changes to `*.rej` files instead of using conflict markers
     #[clap(long)]
-    docker_image_description: String,
-    #[clap(flatten)]
-    #[serde(flatten)]
-    common: CommonData,
+    reject: bool,
+    /// Path to the project to be upgraded
+    #[clap(value_hint = clap::ValueHint::DirPath)]
+    project_path: PathBuf,
 }
 
-#[derive(Parser, Debug, Serialize, Deserialize)]
-struct MesonData {
-    /// Kind of a new meson project
-    #[clap(long, short, value_parser = project_kind, default_value = "c")]
-    kind: ProjectKind,
-    #[clap(flatten)]
-    #[serde(flatten)]
-    common: CommonData,
+#[derive(Parser, Debug)]
+struct CheckData {
+    /// Compare every generated file, not only the CI, Docker, license and REUSE ones
+    #[clap(long)]
+    all: bool,
+    /// Path to the project to be checked
+    #[clap(value_hint = clap::ValueHint::DirPath)]
+    project_path: PathBuf,
 }
 
-#[derive(Parser, Debug, Serialize, Deserialize)]
-struct MavenData {
-    /// Java group.
-    group: String,
-    #[clap(flatten)]
-    #[serde(flatten)]
-    common: CommonData,
+#[derive(Parser, Debug)]
+struct MigrateData {
+    /// Path to the project to be migrated
+    #[clap(value_hint = clap::ValueHint::DirPath)]
+    project_path: PathBuf,
 }
 
-fn project_kind(
+#[derive(Parser, Debug)]
+struct ListData {
+    /// What to list
+    #[clap(value_enum, default_value = "toolchains")]
+    what: Listing,
+    /// Show only the entries containing this text, ignoring case
+    search: Option<String>,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Listing {
+    /// Built-in toolchains and template packs
+    Toolchains,
+    /// SPDX licenses which can be used in `--license`
+    Licenses,
+}
+
+#[derive(Parser, Debug)]
+struct DescribeData {
+    /// Toolchain to be described
+    toolchain: String,
+}
+
+#[derive(Parser, Debug)]
+struct EjectData {
+    /// Toolchain whose templates are copied
+    toolchain: String,
+    /// Template to be copied, by name, e.g. `ci.github`, or by file name,
+    /// e.g. `github.yml`, all of them when missing
+    template: Option<String>,
+    /// Replace the templates already copied
+    #[clap(long)]
+    force: bool,
+}
+
+#[derive(Parser, Debug)]
+struct PreviewData {
+    /// Toolchain the template belongs to
+    toolchain: String,
+    /// Template to be rendered, by name, e.g. `ci.github`, or by file name,
+    /// e.g. `github.yml`
+    template: String,
+    /// Render this file in place of the template
+    #[clap(long, value_hint = clap::ValueHint::FilePath)]
+    file: Option<PathBuf>,
+    /// Set the project name, license, branch or a toolchain input, it can be repeated
+    #[clap(long = "input", value_name = "KEY=VALUE", value_parser = key_value)]
+    inputs: Vec<(String, String)>,
+}
+
+fn conflict_policy(
     s: &str,
-) -> Result<ProjectKind, Box<dyn std::error::Error + Send + Sync + 'static>> {
+) -> Result<ConflictPolicy, Box<dyn std::error::Error + Send + Sync + 'static>> {
     match s {
-        "c" => Ok(ProjectKind::C),
-        "c++" => Ok(ProjectKind::Cxx),
-        _ => Err(format!("{s} is not a valid meson project kind.").into()),
+        "abort" => Ok(ConflictPolicy::Abort),
+        "skip" => Ok(ConflictPolicy::Skip),
+        "overwrite" => Ok(ConflictPolicy::Overwrite),
+        "backup" => Ok(ConflictPolicy::Backup),
+        _ => Err(format!("{s} is not a valid conflict policy.").into()),
     }
 }
 
+fn reuse_format(
+    s: &str,
+) -> Result<ReuseFormat, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    s.parse::<ReuseFormat>().map_err(|e| e.to_string().into())
+}
+
+// The toolchain commands are built from the registry
 #[derive(Parser, Debug)]
 enum Cmd {
-    /// Generate a CI for a cargo project.
-    Cargo(CargoData),
-    /// Generate a new maven project
-    Maven(MavenData),
-    /// Generate a new meson project
-    Meson(MesonData),
-    /// Generate a new poetry project.
-    Poetry(CommonData),
-    /// Generate a new yarn project.
-    Yarn(CommonData),
-}
-
-fn local_config() -> anyhow::Result<PathBuf> {
+    /// Upgrade a generated project to the current templates.
+    Upgrade(UpgradeData),
+    /// Check that the generated files have not drifted from the templates.
+    Check(CheckData),
+    /// Replace the `.reuse/dep5` file of a project with a `REUSE.toml` one.
+    Migrate(MigrateData),
+    /// List the available toolchains or licenses.
+    List(ListData),
+    /// Describe the files and the inputs of a toolchain.
+    Describe(DescribeData),
+    /// Copy the built-in templates of a toolchain to the user templates directory.
+    Eject(EjectData),
+    /// Render a template of a toolchain to the standard output.
+    Preview(PreviewData),
+}
+
+// Licenses offered by the questionnaire, any other SPDX id can be typed
+const COMMON_LICENSES: &[&str] = &[
+    "MIT",
+    "Apache-2.0",
+    "BSD-3-Clause",
+    "BSD-2-Clause",
+    "GPL-3.0-or-later",
+    "GPL-2.0-or-later",
+    "LGPL-3.0-or-later",
+    "AGPL-3.0-or-later",
+    "MPL-2.0",
+    "ISC",
+    "Unlicense",
+];
+
+/// Fills in the inputs missing from the command line and the configuration.
+///
+/// The questions are asked only when the standard input and error are
+/// terminals and the `CI` variable is not set, otherwise a missing input
+/// is an error.
+struct Questionnaire {
+    // Prompts are allowed
+    enabled: bool,
+    // Every input is asked, not only the missing ones
+    all: bool,
+}
+
+impl Questionnaire {
+    fn new(interactive: bool) -> anyhow::Result<Self> {
+        let terminal = std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+        if interactive && !terminal {
+            bail!("--interactive needs a terminal");
+        }
+
+        Ok(Self {
+            enabled: interactive || (terminal && std::env::var_os("CI").is_none()),
+            all: interactive,
+        })
+    }
+
+    fn text(
+        &self,
+        prompt: &str,
+        current: Option<String>,
+        argument: &str,
+        validate: impl Fn(&str) -> Result<(), String>,
+    ) -> anyhow::Result<String> {
+        match current {
+            Some(value) if !self.all => Ok(value),
+            None if !self.enabled => bail!("Missing value for {argument}"),
+            current => {
+                let mut input = Input::<String>::new()
+                    .with_prompt(prompt)
+                    .validate_with(|value: &String| validate(value));
+                if let Some(current) = current {
+                    input = input.default(current);
+                }
+                Ok(input.interact_text()?)
+            }
+        }
+    }
+
+    fn select(
+        &self,
+        prompt: &str,
+        choices: &[String],
+        current: Option<String>,
+        argument: &str,
+    ) -> anyhow::Result<String> {
+        match current {
+            Some(value) if !self.all => Ok(value),
+            None if !self.enabled => bail!("Missing value for {argument}"),
+            current => {
+                let default = current
+                    .and_then(|current| choices.iter().position(|choice| *choice == current))
+                    .unwrap_or_default();
+                let index = Select::new()
+                    .with_prompt(prompt)
+                    .items(choices)
+                    .default(default)
+                    .interact()?;
+                Ok(choices[index].to_owned())
+            }
+        }
+    }
+
+    fn confirm(
+        &self,
+        prompt: &str,
+        current: Option<String>,
+        argument: &str,
+    ) -> anyhow::Result<String> {
+        match current {
+            Some(value) if !self.all => Ok(value),
+            None if !self.enabled => bail!("Missing value for {argument}"),
+            current => {
+                let default = current.and_then(|current| current.parse().ok());
+                let mut confirm = Confirm::new().with_prompt(prompt);
+                if let Some(default) = default {
+                    confirm = confirm.default(default);
+                }
+                Ok(confirm.interact()?.to_string())
+            }
+        }
+    }
+
+    fn license(&self, current: String) -> anyhow::Result<String> {
+        if !self.all {
+            return Ok(current);
+        }
+
+        let mut ids: Vec<&str> = COMMON_LICENSES.to_vec();
+        if !ids.contains(&current.as_str()) {
+            ids.insert(0, &current);
+        }
+        let mut items: Vec<String> = ids
+            .iter()
+            .map(|id| match id.parse::<&dyn license::License>() {
+                Ok(license) => format!("{id} ({})", license.name()),
+                Err(_) => id.to_string(),
+            })
+            .collect();
+        items.push("Other SPDX expression".to_owned());
+
+        let default = ids.iter().position(|id| *id == current).unwrap_or_default();
+        let index = Select::new()
+            .with_prompt("License")
+            .items(&items)
+            .default(default)
+            .interact()?;
+
+        match ids.get(index) {
+            Some(id) => Ok(id.to_string()),
+            None => Ok(Input::<String>::new()
+                .with_prompt("SPDX license expression")
+                .validate_with(|expression: &String| {
+                    from_expression(expression)
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                })
+                .interact_text()?),
+        }
+    }
+
+    /// Asks the inputs shared by every toolchain and returns the project path.
+    fn common(&self, data: &mut CommonData) -> anyhow::Result<PathBuf> {
+        let project_path = self.text(
+            "Project path",
+            data.project_path
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            "the project path",
+            not_empty,
+        )?;
+        let project_path = PathBuf::from(project_path);
+        data.project_path = Some(project_path.clone());
+
+        if self.all {
+            data.name = Input::<String>::new()
+                .with_prompt("Project name, empty to use the last component of the path")
+                .default(data.name.clone())
+                .allow_empty(true)
+                .interact_text()?;
+        }
+        data.license = self.license(std::mem::take(&mut data.license))?;
+        data.branch = self.text(
+            "GitHub branch",
+            Some(data.branch.clone()),
+            "--branch",
+            not_empty,
+        )?;
+
+        Ok(project_path)
+    }
+
+    /// Asks the author and the copyright of the project, only when every
+    /// input is asked.
+    fn authorship(&self, data: &mut CommonData) -> anyhow::Result<()> {
+        if !self.all {
+            return Ok(());
+        }
+        for (prompt, value) in [
+            ("Author", &mut data.author),
+            ("Author email", &mut data.email),
+            (
+                "Copyright holder, empty to use the author",
+                &mut data.copyright_holder,
+            ),
+            (
+                "Copyright year, empty to use the current one",
+                &mut data.year,
+            ),
+        ] {
+            *value = Input::<String>::new()
+                .with_prompt(prompt)
+                .default(value.clone())
+                .allow_empty(true)
+                .interact_text()?;
+        }
+
+        Ok(())
+    }
+
+    /// Asks the inputs of a toolchain which have no value, skipping the
+    /// ones of the components not generated.
+    fn inputs(
+        &self,
+        schema: &[ToolchainInput],
+        options: &Options,
+        inputs: &mut BTreeMap<String, String>,
+    ) -> anyhow::Result<()> {
+        for input in schema {
+            if let Some(component) = &input.component {
+                if !options.includes(component) {
+                    continue;
+                }
+            }
+            let name = &input.name;
+            let prompt = if input.description.is_empty() {
+                name.as_str()
+            } else {
+                input.description.as_str()
+            };
+            let current = inputs.get(name).cloned().or_else(|| input.default.clone());
+            let argument = format!("--{}", flag(name));
+            let value = match input.kind {
+                InputKind::String => self.text(prompt, current, &argument, |_| Ok(()))?,
+                InputKind::Bool => self.confirm(prompt, current, &argument)?,
+                InputKind::Integer => self.text(prompt, current, &argument, |value| {
+                    value
+                        .parse::<i64>()
+                        .map(|_| ())
+                        .map_err(|_| format!("{value} is not an integer"))
+                })?,
+                InputKind::Choice => self.select(prompt, &input.choices, current, &argument)?,
+            };
+            inputs.insert(name.to_owned(), value);
+        }
+
+        Ok(())
+    }
+}
+
+fn not_empty(value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        Err("The value cannot be empty".to_owned())
+    } else {
+        Ok(())
+    }
+}
+
+// Command line flag of an input, the input name is accepted too
+fn flag(name: &str) -> String {
+    name.replace('_', "-")
+}
+
+// How an input is passed on the command line
+fn input_usage(input: &ToolchainInput) -> String {
+    match input.short {
+        _ if input.positional => input.name.to_uppercase(),
+        Some(short) => format!("-{short}, --{}", flag(&input.name)),
+        None => format!("--{}", flag(&input.name)),
+    }
+}
+
+fn toolchain_command(toolchain: &dyn Toolchain) -> Command {
+    let cmd = Command::new(toolchain.name().to_owned()).about(toolchain.description().to_owned());
+    let (positional, options): (Vec<_>, Vec<_>) = toolchain
+        .inputs()
+        .into_iter()
+        .partition(|input| input.positional);
+
+    // The positional inputs come before the project path
+    let cmd = positional.into_iter().fold(cmd, |cmd, input| {
+        let arg = Arg::new(input.name.to_owned()).value_name(input.name.to_uppercase());
+        cmd.arg(input_arg(arg, input))
+    });
+
+    options
+        .into_iter()
+        .fold(CommonData::augment_args(cmd), |cmd, input| {
+            let mut arg = Arg::new(input.name.to_owned()).long(flag(&input.name));
+            if flag(&input.name) != input.name {
+                arg = arg.alias(input.name.to_owned());
+            }
+            if let Some(short) = input.short {
+                arg = arg.short(short);
+            }
+            cmd.arg(input_arg(arg, input))
+        })
+}
+
+fn input_arg(arg: Arg, input: ToolchainInput) -> Arg {
+    let mut arg = arg.help(input.description.to_owned());
+    if let Some(default) = &input.default {
+        let help = format!("{} [default: {default}]", input.description);
+        arg = arg.help(help.trim_start().to_owned());
+    }
+    if input.kind == InputKind::Choice {
+        arg = arg.value_parser(PossibleValuesParser::new(input.choices));
+    }
+    arg
+}
+
+/// A `hooks` table of the configuration.
+#[derive(Debug, Default, Deserialize)]
+struct HooksConfig {
+    /// Commands run after the default ones
+    #[serde(default)]
+    run: Vec<String>,
+    /// Whether the default hooks of the toolchain are run
+    defaults: Option<bool>,
+}
+
+/// The `git` table of the configuration.
+#[derive(Debug, Default, Deserialize)]
+struct GitConfig {
+    /// Initialise a repository
+    #[serde(default)]
+    init: bool,
+    /// Commit the generated files
+    #[serde(default)]
+    commit: bool,
+    /// Author of the initial commit, in the `Name <email>` form
+    author: Option<String>,
+}
+
+/// One or more paths of an annotation.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AnnotationPaths {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// An annotation of the `reuse` table of the configuration.
+#[derive(Debug, Deserialize)]
+struct AnnotationConfig {
+    /// Paths relative to the project root
+    path: AnnotationPaths,
+    /// SPDX expression of the licenses
+    license: String,
+    /// Copyright notice, the one of the project by default
+    #[serde(default)]
+    copyright: String,
+}
+
+impl From<AnnotationConfig> for Annotation {
+    fn from(annotation: AnnotationConfig) -> Self {
+        Self {
+            paths: match annotation.path {
+                AnnotationPaths::One(path) => vec![path],
+                AnnotationPaths::Many(paths) => paths,
+            },
+            license: annotation.license,
+            copyright: annotation.copyright,
+        }
+    }
+}
+
+/// The `reuse` table of the configuration.
+#[derive(Debug, Default, Deserialize)]
+struct ReuseConfig {
+    /// Licensing information of some paths
+    #[serde(default)]
+    annotations: Vec<AnnotationConfig>,
+}
+
+/// A license of the `licenses` table of the configuration, keyed by its
+/// `LicenseRef-<name>` identifier.
+#[derive(Debug, Deserialize)]
+struct LicenseConfig {
+    /// Full name of the license
+    name: String,
+    /// Path to the text, relative to the configuration file
+    text: RelativePathBuf,
+    /// Notice put at the top of the files
+    header: Option<String>,
+}
+
+impl LicenseConfig {
+    fn into_license(self, id: String) -> anyhow::Result<CustomLicense> {
+        if !id.starts_with("LicenseRef-") {
+            bail!("The custom license {id} is not in the LicenseRef-<name> form");
+        }
+        Ok(CustomLicense {
+            id,
+            name: self.name,
+            text: self.text.relative(),
+            header: self.header,
+        })
+    }
+}
+
+fn config_string(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .map(str::to_owned)
+        .or_else(|| value.to_bool().map(|b| b.to_string()))
+        .or_else(|| value.to_i128().map(|n| n.to_string()))
+        .or_else(|| value.to_f64().map(|n| n.to_string()))
+}
+
+fn vars_table(value: Option<Value>) -> BTreeMap<String, String> {
+    value
+        .and_then(|value| value.into_dict())
+        .map(|dict| {
+            dict.iter()
+                .filter_map(|(key, value)| Some((key.to_owned(), config_string(value)?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn local_config_dir() -> anyhow::Result<PathBuf> {
     let config_dir = std::env::var("XDG_CONFIG_HOME")
         .map(PathBuf::from)
         .or_else(|_| {
@@ -181,20 +746,424 @@ fn local_config() -> anyhow::Result<PathBuf> {
                 .ok_or_else(|| anyhow!("Cannot find the home directory"))
         })?;
 
-    Ok(config_dir.join("sifis-generate").join("config.toml"))
+    Ok(config_dir.join("sifis-generate"))
+}
+
+fn print_plan(plan: &Plan) {
+    println!("Directories:");
+    for dir in &plan.dirs {
+        println!("  {}", dir.display());
+    }
+
+    println!("Files:");
+    for file in &plan.files {
+        println!("  {} (from {})", file.path.display(), file.template);
+    }
+
+    if !plan.removed.is_empty() {
+        println!("Removed files:");
+        for path in &plan.removed {
+            println!("  {}", path.display());
+        }
+    }
+
+    if !plan.hooks.is_empty() {
+        println!("Hooks:");
+        for hook in &plan.hooks {
+            println!("  {hook}");
+        }
+    }
+
+    for file in &plan.files {
+        println!("\n==> {} <==", file.path.display());
+        print!("{}", file.content);
+    }
+}
+
+fn matches_search(search: Option<&str>, fields: &[&str]) -> bool {
+    search.is_none_or(|search| {
+        let search = search.to_lowercase();
+        fields
+            .iter()
+            .any(|field| field.to_lowercase().contains(&search))
+    })
+}
+
+fn list(registry: &Registry, data: &ListData, options: &Options) {
+    let search = data.search.as_deref();
+    match data.what {
+        Listing::Toolchains => {
+            let toolchains: Vec<&dyn Toolchain> = registry
+                .iter()
+                .filter(|toolchain| {
+                    matches_search(search, &[toolchain.name(), toolchain.description()])
+                })
+                .collect();
+            let width = toolchains
+                .iter()
+                .map(|toolchain| toolchain.name().len())
+                .max()
+                .unwrap_or_default();
+            for toolchain in toolchains {
+                let modes: Vec<&str> = toolchain.modes().iter().map(Mode::as_str).collect();
+                println!(
+                    "{:width$}  {:7}  {}",
+                    toolchain.name(),
+                    modes.join(","),
+                    toolchain.description()
+                );
+            }
+        }
+        Listing::Licenses => {
+            for license in &options.licenses {
+                if matches_search(search, &[&license.id, &license.name]) {
+                    println!("{:36}  {}", license.id, license.name);
+                }
+            }
+            // Only the licenses whose text is embedded can be used
+            for (id, name, _) in spdx::identifiers::LICENSES {
+                if id.parse::<&dyn license::License>().is_ok()
+                    && matches_search(search, &[id, name])
+                {
+                    println!("{id:36}  {name}");
+                }
+            }
+        }
+    }
+}
+
+// Returns a non-empty value of the configuration
+fn configured(config: &Figment, key: &str) -> Option<String> {
+    config
+        .find_value(key)
+        .ok()
+        .and_then(|value| config_string(&value))
+        .filter(|value| !value.is_empty())
+}
+
+/// Returns a request with the values of the configuration section of a
+/// toolchain, and placeholders in place of the missing inputs.
+///
+/// The placeholders of the names are the names of the inputs themselves, so
+/// that they pass the checks of the toolchain.
+fn placeholder_request(
+    toolchain: &dyn Toolchain,
+    config: &Figment,
+    options: Options,
+) -> anyhow::Result<GenerationRequest> {
+    let mut request = GenerationRequest::new("name");
+    request.name = "name".to_owned();
+    request.options = options;
+    if let Some(format) = configured(config, "reuse_format") {
+        request.options.reuse_format = format.parse()?;
+    }
+    if let Some(license) = configured(config, "license") {
+        request.license = license;
+    }
+    if let Some(branch) = configured(config, "branch") {
+        request.branch = branch;
+    }
+    request.author = configured(config, "author").unwrap_or_default();
+    request.email = configured(config, "email").unwrap_or_default();
+    request.copyright_holder = configured(config, "copyright_holder").unwrap_or_default();
+    request.year = configured(config, "year").unwrap_or_default();
+    for input in toolchain.inputs() {
+        let placeholder = match input.kind {
+            InputKind::String => input.name.clone(),
+            InputKind::Bool => "false".to_owned(),
+            InputKind::Integer => "0".to_owned(),
+            InputKind::Choice => input.choices.first().cloned().unwrap_or_default(),
+        };
+        let value = configured(config, &input.name)
+            .or(input.default)
+            .unwrap_or(placeholder);
+        request.inputs.insert(input.name, value);
+    }
+
+    Ok(request)
+}
+
+/// Prints the inputs of a toolchain, with their defaults from the
+/// configuration, and the files it generates grouped by component.
+fn describe(toolchain: &dyn Toolchain, config: &Figment, options: Options) -> anyhow::Result<()> {
+    let config = config.clone().select(toolchain.name());
+    let configured = |key: &str| configured(&config, key);
+    let schema = toolchain.inputs();
+
+    // Renders the files with placeholders in place of the required inputs
+    let request = placeholder_request(toolchain, &config, options)?;
+    let plan = toolchain.plan(&request)?;
+
+    let modes: Vec<&str> = toolchain.modes().iter().map(Mode::as_str).collect();
+    println!("{}: {}", toolchain.name(), toolchain.description());
+    println!("Modes: {}", modes.join(", "));
+
+    let mut inputs = vec![
+        (
+            "--name".to_owned(),
+            "Name of the project".to_owned(),
+            Some(configured("name").unwrap_or("the project directory name".to_owned())),
+        ),
+        (
+            "--license".to_owned(),
+            "License of the project".to_owned(),
+            Some(request.license.clone()),
+        ),
+        (
+            "--branch".to_owned(),
+            "Branch the CI runs on".to_owned(),
+            Some(request.branch.clone()),
+        ),
+        (
+            "--author".to_owned(),
+            "Author of the project".to_owned(),
+            Some(configured("author").unwrap_or("the git user.name".to_owned())),
+        ),
+        (
+            "--email".to_owned(),
+            "Email of the author".to_owned(),
+            Some(configured("email").unwrap_or("the git user.email".to_owned())),
+        ),
+        (
+            "--copyright-holder".to_owned(),
+            "Holder of the copyright".to_owned(),
+            Some(configured("copyright_holder").unwrap_or("the author".to_owned())),
+        ),
+        (
+            "--year".to_owned(),
+            "Year of the copyright notices".to_owned(),
+            Some(configured("year").unwrap_or("the current year".to_owned())),
+        ),
+        (
+            "--reuse-format".to_owned(),
+            "Format of the REUSE metadata".to_owned(),
+            Some(request.options.reuse_format.as_str().to_owned()),
+        ),
+    ];
+    for input in &schema {
+        let mut description = input.description.clone();
+        if let Some(component) = &input.component {
+            description = format!("{description}, used by the {component} component");
+        }
+        if input.kind == InputKind::Choice {
+            description = format!("{description}, one of {}", input.choices.join(", "));
+        }
+        inputs.push((
+            input_usage(input),
+            description.trim_start_matches(", ").to_owned(),
+            configured(&input.name).or_else(|| input.default.clone()),
+        ));
+    }
+    let width = inputs
+        .iter()
+        .map(|(flag, ..)| flag.len())
+        .max()
+        .unwrap_or_default();
+    println!("\nInputs:");
+    for (flag, description, default) in inputs {
+        match default {
+            Some(default) => println!("  {flag:width$}  {description} [default: {default}]"),
+            None => println!("  {flag:width$}  {description} [required]"),
+        }
+    }
+
+    // The files of no component come last
+    let mut components: BTreeMap<(bool, String), Vec<String>> = BTreeMap::new();
+    for file in &plan.files {
+        let path = file.path.strip_prefix(&plan.root).unwrap_or(&file.path);
+        let key = match &file.component {
+            Some(component) => (false, component.to_owned()),
+            None => (true, "always generated".to_owned()),
+        };
+        components.entry(key).or_default().push(format!(
+            "{} (from {})",
+            path.display(),
+            file.template
+        ));
+    }
+    println!("\nFiles:");
+    for ((_, component), files) in components {
+        println!("  {component}");
+        for file in files {
+            println!("    {file}");
+        }
+    }
+
+    if !plan.hooks.is_empty() {
+        println!("\nHooks:");
+        for hook in &plan.hooks {
+            println!("  {hook}");
+        }
+    }
+
+    Ok(())
+}
+
+// Finds a built-in template by name or by file name
+fn find_template(toolchain: &dyn Toolchain, template: &str) -> Option<&'static BuiltinTemplate> {
+    toolchain
+        .templates()
+        .iter()
+        .find(|builtin| builtin.name == template || builtin.file == template)
+}
+
+/// Copies the built-in templates of a toolchain into `templates_dir`, where
+/// they replace the built-in ones.
+fn eject(toolchain: &dyn Toolchain, data: &EjectData, templates_dir: &Path) -> anyhow::Result<()> {
+    let templates: Vec<&BuiltinTemplate> = match &data.template {
+        Some(template) => vec![find_template(toolchain, template).with_context(|| {
+            format!(
+                "The {} toolchain has no {template} template",
+                toolchain.name()
+            )
+        })?],
+        None => toolchain.templates().iter().collect(),
+    };
+    if templates.is_empty() {
+        bail!(
+            "The {} toolchain has no built-in templates",
+            toolchain.name()
+        );
+    }
+
+    let dir = templates_dir.join(toolchain.name());
+    create_dir_all(&dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+    for template in templates {
+        let path = dir.join(template.file);
+        if path.exists() && !data.force {
+            warn!(
+                "{} already exists, pass --force to replace it",
+                path.display()
+            );
+            continue;
+        }
+        info!("Copying {} to {}", template.name, path.display());
+        write(&path, template.source)
+            .with_context(|| format!("Cannot write {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Renders a template of a toolchain with the configured values, the
+/// ones passed with `--input` and placeholders for the missing inputs.
+fn preview(
+    toolchain: &dyn Toolchain,
+    data: &PreviewData,
+    config: &Figment,
+    options: Options,
+) -> anyhow::Result<()> {
+    let config = config.clone().select(toolchain.name());
+    let mut request = placeholder_request(toolchain, &config, options)?;
+
+    for (key, value) in &data.inputs {
+        match key.as_str() {
+            "name" => request.name = value.to_owned(),
+            "license" => request.license = value.to_owned(),
+            "branch" => request.branch = value.to_owned(),
+            key if request.inputs.contains_key(key) => {
+                request.inputs.insert(key.to_owned(), value.to_owned());
+            }
+            key => bail!(
+                "{key} is not an input of the {} toolchain, use --set for the other variables",
+                toolchain.name()
+            ),
+        }
+    }
+
+    let name = find_template(toolchain, &data.template)
+        .map(|template| template.name.to_owned())
+        .unwrap_or_else(|| data.template.clone());
+    if let Some(file) = &data.file {
+        let source =
+            read_to_string(file).with_context(|| format!("Cannot read {}", file.display()))?;
+        request
+            .options
+            .template_sources
+            .insert(name.clone(), source);
+    }
+
+    let plan = toolchain.plan(&request)?;
+    let file = plan
+        .files
+        .iter()
+        .find(|file| file.template == name)
+        .with_context(|| {
+            format!(
+                "The {} toolchain has no {} template",
+                toolchain.name(),
+                data.template
+            )
+        })?;
+    print!("{}", file.content);
+
+    Ok(())
+}
+
+/// Fails when any of the global `flags` is passed to `command`, which
+/// ignores it.
+fn reject_flags(matches: &ArgMatches, command: &str, flags: &[&str]) -> anyhow::Result<()> {
+    for flag in flags {
+        if matches.value_source(flag) == Some(ValueSource::CommandLine) {
+            bail!("--{} cannot be used with {command}", flag.replace('_', "-"));
+        }
+    }
+
+    Ok(())
+}
+
+fn write_archive(plan: &Plan, archive: &Path) -> anyhow::Result<()> {
+    let name = archive.to_string_lossy();
+    let root = plan.root.parent().unwrap_or(&plan.root);
+    let file =
+        File::create(archive).with_context(|| format!("Cannot create {}", archive.display()))?;
+
+    if name.ends_with(".zip") {
+        let mut sink = ZipSink::new(file, root);
+        plan.write_to(&mut sink)?;
+        sink.finish()?;
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        let mut sink = TarGzSink::new(file, root);
+        plan.write_to(&mut sink)?;
+        sink.finish()?;
+    } else {
+        bail!("{name} is not a .tar.gz or .zip archive");
+    }
+
+    Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
-    let cmd = Opts::command();
+    let config_dir = local_config_dir()?;
+    let packs_dirs = vec![config_dir.join("packs")];
+    let registry = Registry::with_packs(&packs_dirs).context("Cannot load the template packs")?;
+
+    let cmd = registry.iter().fold(Opts::command(), |cmd, toolchain| {
+        cmd.subcommand(toolchain_command(toolchain))
+    });
     let matches = cmd.get_matches();
     let verbose = matches.get_flag("verbose");
+    let dry_run = matches.get_flag("dry_run");
+    let output_archive = matches.get_one::<PathBuf>("output_archive").cloned();
+    let no_hooks = matches.get_flag("no_hooks");
+    let mut git_init_flag = matches.get_flag("git_init");
+    let mut git_commit_flag = matches.get_flag("git_commit");
+    let mut git_author = matches.get_one::<GitAuthor>("git_author").cloned();
+    let questionnaire = Questionnaire::new(matches.get_flag("interactive"))?;
 
     let config_file = if let Some(cfg) = matches.get_one::<PathBuf>("config") {
         cfg.to_owned()
     } else {
-        local_config()?
+        config_dir.join("config.toml")
     };
 
+    let mut options = Options::default();
+    if let Some(templates_dir) = matches.get_one::<PathBuf>("templates_dir") {
+        options.templates_dirs.push(templates_dir.to_owned());
+    }
+    options.templates_dirs.push(config_dir.join("templates"));
+    options.packs_dirs = packs_dirs;
+
     let filter_layer = EnvFilter::try_from_default_env()
         .or_else(|_| {
             if verbose {
@@ -215,67 +1184,233 @@ fn main() -> anyhow::Result<()> {
         .subcommand()
         .ok_or_else(|| anyhow!("Missing command"))?;
 
+    // Only the commands computing a plan write it
+    if registry.get(sub.0).is_none() && !matches!(sub.0, "upgrade" | "migrate") {
+        reject_flags(&matches, sub.0, &["dry_run", "output_archive"])?;
+    }
+    // Only the new projects get a repository of their own
+    let new_project = registry
+        .get(sub.0)
+        .is_some_and(|toolchain| toolchain.modes().contains(&Mode::Project));
+    if !new_project {
+        reject_flags(&matches, sub.0, &["git_init", "git_commit", "git_author"])?;
+    }
+
     let config = Figment::new()
         .merge(Toml::string(DEFAULT_CONF).nested())
-        .merge(Toml::file(config_file).nested());
+        .merge(Toml::file(&config_file).nested());
 
-    match sub {
-        ("cargo", matches) => {
-            let config = config
-                .merge(ClapSerialized::<CargoData>::globals(matches.clone()))
-                .select("cargo");
-            let data: CargoData = config.extract()?;
-            Cargo::new(&data.docker_image_description).create_ci(
-                &data.common.name,
-                &data.common.project_path,
-                &data.common.license,
-                &data.common.branch,
-            )
+    // Variables from `[vars]`, then `[<toolchain>.vars]`, then `--set`
+    let mut vars = vars_table(
+        Figment::from(Toml::file(&config_file))
+            .find_value("vars")
+            .ok(),
+    );
+    vars.extend(vars_table(
+        config.clone().select(sub.0).find_value("vars").ok(),
+    ));
+    vars.extend(
+        matches
+            .get_many::<(String, String)>("vars")
+            .into_iter()
+            .flatten()
+            .cloned(),
+    );
+    options.vars = vars;
+
+    let git = match Figment::from(Toml::file(&config_file)).extract_inner::<GitConfig>("git") {
+        Ok(git) => git,
+        Err(e) if e.missing() => GitConfig::default(),
+        Err(e) => return Err(e.into()),
+    };
+    git_commit_flag |= git.commit;
+    git_init_flag |= git.init || git_commit_flag;
+    if git_author.is_none() {
+        git_author = git.author.as_deref().map(str::parse).transpose()?;
+    }
+
+    // Licenses outside of the SPDX list, from `[licenses.<id>]`
+    let licenses = match Figment::from(Toml::file(&config_file))
+        .extract_inner::<BTreeMap<String, LicenseConfig>>("licenses")
+    {
+        Ok(licenses) => licenses,
+        Err(e) if e.missing() => BTreeMap::new(),
+        Err(e) => return Err(e.into()),
+    };
+    options.licenses = licenses
+        .into_iter()
+        .map(|(id, license)| license.into_license(id))
+        .collect::<anyhow::Result<_>>()?;
+
+    // Annotations from `[[reuse.annotations]]`
+    let reuse = match Figment::from(Toml::file(&config_file)).extract_inner::<ReuseConfig>("reuse")
+    {
+        Ok(reuse) => reuse,
+        Err(e) if e.missing() => ReuseConfig::default(),
+        Err(e) => return Err(e.into()),
+    };
+    options.annotations = reuse
+        .annotations
+        .into_iter()
+        .map(Annotation::from)
+        .collect();
+
+    // Hooks from `[hooks]`, then `[<toolchain>.hooks]`
+    let hooks = [
+        Figment::from(Toml::file(&config_file)).extract_inner::<HooksConfig>("hooks"),
+        config.clone().select(sub.0).extract_inner("hooks"),
+    ];
+    for hooks in hooks {
+        let hooks = match hooks {
+            Ok(hooks) => hooks,
+            Err(e) if e.missing() => HooksConfig::default(),
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(defaults) = hooks.defaults {
+            options.skip_default_hooks = !defaults;
         }
-        ("maven", matches) => {
-            let config = config
-                .merge(ClapSerialized::<MavenData>::globals(matches.clone()))
-                .select("maven");
-            let data: MavenData = config.extract()?;
-            Maven::new(&data.group).create_project(
-                &data.common.name,
-                &data.common.project_path,
-                &data.common.license,
-                &data.common.branch,
-            )
+        options.hooks.extend(hooks.run);
+    }
+
+    let (plan, on_conflict, branch) = match sub {
+        ("upgrade", matches) => {
+            let data = UpgradeData::from_arg_matches(matches)?;
+            let style = if data.reject {
+                MergeStyle::Reject
+            } else {
+                MergeStyle::Markers
+            };
+            let upgrade = upgrade(&data.project_path, style, &options)?;
+            for path in &upgrade.updated {
+                info!("Updating {}", path.display());
+            }
+            for path in &upgrade.merged {
+                info!("Merging {}", path.display());
+            }
+            for path in &upgrade.conflicts {
+                warn!("Conflicts in {}", path.display());
+            }
+            for path in &upgrade.skipped {
+                warn!(
+                    "Skipping {}, its generated version is unknown",
+                    path.display()
+                );
+            }
+            (upgrade.plan, ConflictPolicy::Overwrite, None)
         }
-        ("meson", matches) => {
-            let config = config
-                .merge(ClapSerialized::<MesonData>::globals(matches.clone()))
-                .select("meson");
-            let data: MesonData = config.extract()?;
-            Meson::new(data.kind).create_project(
-                &data.common.name,
-                &data.common.project_path,
-                &data.common.license,
-                &data.common.branch,
-            )
+        ("check", matches) => {
+            let data = CheckData::from_arg_matches(matches)?;
+            let drifts = check(&data.project_path, data.all, &options)?;
+            for drift in &drifts {
+                print!("{}", drift.diff());
+            }
+            if !drifts.is_empty() {
+                bail!("{} files have drifted from the templates", drifts.len());
+            }
+            return Ok(());
         }
-        ("poetry", matches) => {
-            let config = config
-                .merge(ClapSerialized::<CommonData>::globals(matches.clone()))
-                .select("poetry");
-            let data: CommonData = config.extract()?;
-
-            Poetry::new().create_project(
-                &data.name,
-                &data.project_path,
-                &data.license,
-                &data.branch,
-            )
+        ("migrate", matches) => {
+            let data = MigrateData::from_arg_matches(matches)?;
+            let plan = migrate_dep5(&data.project_path, &options)?;
+            info!(
+                "Replacing {} with {}",
+                data.project_path.join(".reuse/dep5").display(),
+                data.project_path.join("REUSE.toml").display()
+            );
+            (plan, ConflictPolicy::Overwrite, None)
+        }
+        ("list", matches) => {
+            list(&registry, &ListData::from_arg_matches(matches)?, &options);
+            return Ok(());
         }
-        ("yarn", matches) => {
+        ("describe", matches) => {
+            let data = DescribeData::from_arg_matches(matches)?;
+            return describe(registry.find(&data.toolchain)?, &config, options);
+        }
+        ("eject", matches) => {
+            let data = EjectData::from_arg_matches(matches)?;
+            return eject(
+                registry.find(&data.toolchain)?,
+                &data,
+                &options.templates_dirs[0],
+            );
+        }
+        ("preview", matches) => {
+            let data = PreviewData::from_arg_matches(matches)?;
+            return preview(registry.find(&data.toolchain)?, &data, &config, options);
+        }
+        (name, matches) => {
+            let toolchain = registry.find(name)?;
+            let schema = toolchain.inputs();
+            let cli_inputs: BTreeMap<&str, &String> = schema
+                .iter()
+                .filter_map(|input| {
+                    Some((input.name.as_str(), matches.get_one::<String>(&input.name)?))
+                })
+                .collect();
             let config = config
                 .merge(ClapSerialized::<CommonData>::globals(matches.clone()))
-                .select("yarn");
-            let data: CommonData = config.extract()?;
-            Yarn::new().create_ci(&data.name, &data.project_path, &data.license, &data.branch)
+                .merge(Serialized::globals(cli_inputs))
+                .select(name);
+            let mut data: CommonData = config.extract()?;
+            data.select_components(&mut options);
+            let project_path = questionnaire.common(&mut data)?;
+            // The identity configured in git is the default author, its email
+            // is not given to another author
+            let identity = git_identity(&project_path);
+            if data.author.is_empty() {
+                data.author = identity.name.clone();
+            }
+            if data.email.is_empty() && data.author == identity.name {
+                data.email = identity.email;
+            }
+            questionnaire.authorship(&mut data)?;
+            let mut inputs = schema
+                .iter()
+                .filter_map(|input| {
+                    let value = config.find_value(&input.name).ok()?;
+                    Some((input.name.to_owned(), config_string(&value)?))
+                })
+                .collect();
+            questionnaire.inputs(&schema, &options, &mut inputs)?;
+            let request = GenerationRequest {
+                project_path,
+                name: data.name,
+                license: data.license,
+                branch: data.branch,
+                author: data.author,
+                email: data.email,
+                copyright_holder: data.copyright_holder,
+                year: data.year,
+                inputs,
+                options,
+            };
+            let plan = toolchain.plan(&request)?;
+            let branch = Some(request.branch).filter(|_| new_project && git_init_flag);
+            (plan, data.on_conflict, branch)
+        }
+    };
+
+    if dry_run {
+        print_plan(&plan);
+        Ok(())
+    } else if let Some(archive) = output_archive {
+        write_archive(&plan, &archive)
+    } else {
+        if branch.is_some() && plan.root.join(".git").exists() {
+            return Err(Error::RepositoryExists { path: plan.root }.into());
+        }
+        plan.write(on_conflict)?;
+        if let Some(branch) = &branch {
+            git_init(&plan.root, branch)?;
+            // The output of the hooks, such as build directories, is left out
+            if git_commit_flag {
+                git_commit(&plan.root, "Initial commit", git_author.as_ref())?;
+            }
+        }
+        if !no_hooks {
+            plan.run_hooks()?;
         }
-        _ => unreachable!("unexpected command"),
+        Ok(())
     }
 }
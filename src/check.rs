@@ -0,0 +1,78 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use diffy::DiffOptions;
+
+use crate::manifest::relative_path;
+use crate::upgrade::replay;
+use crate::{Manifest, Options, Result, MANIFEST_FILE};
+
+// Template namespaces of the files a project is not expected to edit
+const INFRASTRUCTURE: &[&str] = &[
+    "ci.",
+    "docker.",
+    "Dockerfile",
+    "dep5.",
+    "toml.reuse",
+    "build.license",
+];
+
+/// A generated file whose content differs from its template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    /// Path of the file, relative to the project root.
+    pub path: PathBuf,
+    /// Name of the template the file is rendered from.
+    pub template: String,
+    /// Content rendered from the template.
+    pub expected: String,
+    /// Content on disk, `None` if the file is missing.
+    pub actual: Option<String>,
+}
+
+impl Drift {
+    /// Returns the unified diff turning the rendered content into the one on disk.
+    pub fn diff(&self) -> String {
+        let path = self.path.display();
+        DiffOptions::new()
+            .set_original_filename(format!("a/{path}"))
+            .set_modified_filename(format!("b/{path}"))
+            .create_patch(&self.expected, self.actual.as_deref().unwrap_or_default())
+            .to_string()
+    }
+}
+
+/// Compares the files of a project with its templates rendered in memory,
+/// using the inputs recorded in the project manifest.
+///
+/// Only CI, Docker, license and REUSE files are compared, unless `all`
+/// is set.
+pub fn check(project_path: &Path, all: bool, options: &Options) -> Result<Vec<Drift>> {
+    let manifest = Manifest::read(project_path)?;
+    let plan = replay(&manifest, project_path, options)?;
+
+    let drifts = plan
+        .files
+        .into_iter()
+        .filter(|file| {
+            all || INFRASTRUCTURE
+                .iter()
+                .any(|prefix| file.template.starts_with(prefix))
+        })
+        .filter_map(|file| {
+            let relative = relative_path(&file.path, project_path);
+            if relative == MANIFEST_FILE {
+                return None;
+            }
+            let actual = read_to_string(&file.path).ok();
+            (actual.as_ref() != Some(&file.content)).then(|| Drift {
+                path: PathBuf::from(relative),
+                template: file.template,
+                expected: file.content,
+                actual,
+            })
+        })
+        .collect();
+
+    Ok(drifts)
+}
@@ -0,0 +1,272 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+
+/// Boxed error of a dependency, kept as the source of an [`Error`].
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The errors of the library.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The license is not a known SPDX identifier.
+    #[error("Cannot find the {license} license")]
+    UnknownLicense {
+        /// License as passed.
+        license: String,
+    },
+    /// The license expression is not valid SPDX.
+    #[error("Invalid license expression {expression}: {reason}")]
+    LicenseExpression {
+        /// Expression as passed.
+        expression: String,
+        /// What is wrong with the expression.
+        reason: String,
+    },
+    /// The project name cannot be taken from the project path.
+    #[error("Impossible to get the project name from {}", path.display())]
+    ProjectName {
+        /// Path of the project.
+        path: PathBuf,
+    },
+    /// The project name is not valid in the ecosystem of the toolchain.
+    #[error(
+        "Invalid {toolchain} project name {name}: {reason}{}",
+        suggestion.as_ref().map(|suggestion| format!(", try {suggestion}")).unwrap_or_default()
+    )]
+    InvalidName {
+        /// Name of the toolchain.
+        toolchain: String,
+        /// Name as passed.
+        name: String,
+        /// Which rule the name breaks.
+        reason: String,
+        /// A valid name close to the one passed, if any.
+        suggestion: Option<String>,
+    },
+    /// No toolchain has the given name.
+    #[error("Unknown toolchain {name}")]
+    UnknownToolchain {
+        /// Name of the toolchain.
+        name: String,
+    },
+    /// A selected component is not known to any toolchain.
+    #[error(
+        "The {toolchain} toolchain has no {name} component, available ones: {}",
+        available.join(", ")
+    )]
+    UnknownComponent {
+        /// Name of the toolchain.
+        toolchain: String,
+        /// Name of the component.
+        name: String,
+        /// Components of the toolchain.
+        available: Vec<String>,
+    },
+    /// A required input has no value.
+    #[error("Missing value for the {input} input of {toolchain}")]
+    MissingInput {
+        /// Name of the toolchain.
+        toolchain: String,
+        /// Name of the input.
+        input: String,
+    },
+    /// An input has a value it does not admit.
+    #[error(
+        "Invalid value {value} for {input}: {reason}{}",
+        suggestion.as_ref().map(|suggestion| format!(", try {suggestion}")).unwrap_or_default()
+    )]
+    InvalidInput {
+        /// Name of the input.
+        input: String,
+        /// Value passed.
+        value: String,
+        /// What the input admits.
+        reason: String,
+        /// A valid value close to the one passed, if any.
+        suggestion: Option<String>,
+    },
+    /// A variable passed by the user is already defined by the toolchain.
+    #[error("The {variable} variable is already defined by the {toolchain} toolchain")]
+    VariableConflict {
+        /// Name of the toolchain.
+        toolchain: String,
+        /// Name of the variable.
+        variable: String,
+    },
+    /// A template cannot be parsed or rendered.
+    #[error(
+        "Cannot render the {template} template{}",
+        path.as_ref().map(|path| format!(" into {}", path.display())).unwrap_or_default()
+    )]
+    Template {
+        /// Name of the template.
+        template: String,
+        /// Path of the file rendered from the template, if any.
+        path: Option<PathBuf>,
+        /// Error reported by the template engine.
+        #[source]
+        source: minijinja::Error,
+    },
+    /// A user template does not replace any built-in one.
+    #[error("{} does not match any {toolchain} built-in template", path.display())]
+    UnknownTemplate {
+        /// Name of the toolchain.
+        toolchain: String,
+        /// Path of the user template.
+        path: PathBuf,
+    },
+    /// A template pack cannot be loaded.
+    #[error("Invalid pack {}: {reason}", path.display())]
+    InvalidPack {
+        /// Path of the pack.
+        path: PathBuf,
+        /// What is wrong with the pack.
+        reason: String,
+    },
+    /// A project manifest cannot be parsed or serialized.
+    #[error("Invalid manifest {}", path.display())]
+    Manifest {
+        /// Path of the manifest.
+        path: PathBuf,
+        /// Error reported by the TOML parser or serializer.
+        #[source]
+        source: BoxError,
+    },
+    /// Some files of a plan already exist and the generation was aborted.
+    #[error(
+        "The following files already exist and would be overwritten:\n{}",
+        paths.iter().map(|path| format!("  {}", path.display())).collect::<Vec<_>>().join("\n")
+    )]
+    Conflicts {
+        /// Paths of the existing files.
+        paths: Vec<PathBuf>,
+    },
+    /// A `.reuse/dep5` file cannot be parsed.
+    #[error("Invalid dep5 file {}: {reason}", path.display())]
+    Dep5 {
+        /// Path of the file.
+        path: PathBuf,
+        /// What is wrong with the file.
+        reason: String,
+    },
+    /// A file or a directory cannot be read or written.
+    #[error("Cannot access {}", path.display())]
+    Io {
+        /// Path of the file or the directory.
+        path: PathBuf,
+        /// Error reported by the operating system.
+        #[source]
+        source: std::io::Error,
+    },
+    /// An entry cannot be written to an archive.
+    #[error("Cannot archive {}", path.display())]
+    Archive {
+        /// Path of the entry.
+        path: PathBuf,
+        /// Error reported by the archive writer.
+        #[source]
+        source: BoxError,
+    },
+    /// A hook cannot be parsed or run.
+    #[error("Cannot run {hook}")]
+    Hook {
+        /// Command line of the hook.
+        hook: String,
+        /// Error reported while parsing or spawning the command.
+        #[source]
+        source: BoxError,
+    },
+    /// A hook exited with a failure.
+    #[error("{hook} failed with {status}")]
+    HookFailed {
+        /// Command line of the hook.
+        hook: String,
+        /// Exit status of the command.
+        status: ExitStatus,
+    },
+    /// A git author is not in the `Name <email>` form.
+    #[error("{value} is not in the `Name <email>` form")]
+    InvalidAuthor {
+        /// Author as passed.
+        value: String,
+    },
+    /// The branch name is not valid in git.
+    #[error("{branch} is not a valid branch name")]
+    InvalidBranch {
+        /// Branch name as passed.
+        branch: String,
+    },
+    /// The directory already contains a git repository.
+    #[error("{} is already a git repository", path.display())]
+    RepositoryExists {
+        /// Path of the repository.
+        path: PathBuf,
+    },
+    /// Neither an author was passed nor one is configured in git.
+    #[error("No git author configured, set user.name and user.email")]
+    MissingAuthor,
+    /// A git operation failed.
+    #[error("Git operation failed in {}", path.display())]
+    Git {
+        /// Path of the repository.
+        path: PathBuf,
+        /// Error reported by git.
+        #[source]
+        source: BoxError,
+    },
+}
+
+/// A specialised `Result` type for the library.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Attaches a path to the I/O errors.
+pub(crate) trait WithPath<T> {
+    fn with_path(self, path: impl AsRef<Path>) -> Result<T>;
+}
+
+impl<T> WithPath<T> for std::io::Result<T> {
+    fn with_path(self, path: impl AsRef<Path>) -> Result<T> {
+        self.map_err(|source| Error::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        })
+    }
+}
+
+/// Attaches a template name to the template engine errors.
+pub(crate) trait WithTemplate<T> {
+    fn with_template(self, template: &str) -> Result<T>;
+    fn with_target(self, template: &str, path: &Path) -> Result<T>;
+}
+
+impl<T> WithTemplate<T> for std::result::Result<T, minijinja::Error> {
+    fn with_template(self, template: &str) -> Result<T> {
+        self.map_err(|source| Error::Template {
+            template: template.to_owned(),
+            path: None,
+            source,
+        })
+    }
+
+    fn with_target(self, template: &str, path: &Path) -> Result<T> {
+        self.map_err(|source| Error::Template {
+            template: template.to_owned(),
+            path: Some(path.to_path_buf()),
+            source,
+        })
+    }
+}
+
+/// Attaches a repository path to the git errors.
+pub(crate) trait WithRepository<T> {
+    fn with_repository(self, path: &Path) -> Result<T>;
+}
+
+impl<T, E: Into<BoxError>> WithRepository<T> for std::result::Result<T, E> {
+    fn with_repository(self, path: &Path) -> Result<T> {
+        self.map_err(|source| Error::Git {
+            path: path.to_path_buf(),
+            source: source.into(),
+        })
+    }
+}
@@ -21,3 +21,16 @@ pub(crate) fn comment_license(
 pub(crate) fn hypens_to_underscores(_state: &State, value: String) -> Result<String, Error> {
     Ok(value.replace('-', "_"))
 }
+
+pub(crate) fn toml_string(_state: &State, value: String) -> Result<String, Error> {
+    Ok(toml::Value::String(value).to_string())
+}
+
+pub(crate) fn xml_escape(_state: &State, value: String) -> Result<String, Error> {
+    Ok(value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;"))
+}
@@ -0,0 +1,303 @@
+use std::fs::{read, read_dir, read_link, symlink_metadata};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use gix::bstr::ByteSlice;
+use gix::index::entry::Mode;
+use gix::object::tree::EntryKind;
+use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit};
+use gix::refs::{FullName, Target};
+use gix::worktree::stack::state::ignore::Source;
+use gix::ObjectId;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::error::{WithPath, WithRepository};
+use crate::{Error, Result};
+
+/// Author of the initial commit of a repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitAuthor {
+    /// Name of the author.
+    pub name: String,
+    /// Email of the author.
+    pub email: String,
+}
+
+impl FromStr for GitAuthor {
+    type Err = Error;
+
+    /// Parses an author in the `Name <email>` form.
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((name, email)) = s.trim().strip_suffix('>').and_then(|s| s.rsplit_once('<'))
+        else {
+            return Err(Error::InvalidAuthor {
+                value: s.to_owned(),
+            });
+        };
+
+        Ok(Self {
+            name: name.trim().to_owned(),
+            email: email.trim().to_owned(),
+        })
+    }
+}
+
+/// Returns the `user.name` and `user.email` configured in git, as seen from
+/// `path`: the ones of the repository containing it, if any, or else the
+/// global ones.
+///
+/// The values which are not configured are empty.
+pub fn git_identity(path: &Path) -> GitAuthor {
+    let dir = path
+        .ancestors()
+        .find(|dir| dir.is_dir())
+        .unwrap_or(Path::new("."));
+    let config = match gix::discover(dir) {
+        Ok(repo) => repo.config_snapshot().plumbing().clone(),
+        Err(_) => gix::config::File::from_globals().unwrap_or_default(),
+    };
+    let value = |key: &str| {
+        config
+            .string(key)
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    };
+
+    GitAuthor {
+        name: value("user.name"),
+        email: value("user.email"),
+    }
+}
+
+/// Initialises a git repository in `path`, with `branch` as its default
+/// branch.
+pub fn git_init(path: &Path, branch: &str) -> Result<()> {
+    if path.join(".git").exists() {
+        return Err(Error::RepositoryExists {
+            path: path.to_path_buf(),
+        });
+    }
+    let head =
+        FullName::try_from(format!("refs/heads/{branch}")).map_err(|_| Error::InvalidBranch {
+            branch: branch.to_owned(),
+        })?;
+
+    debug!("Initialising a git repository in {}", path.display());
+    let repo = gix::init(path).with_repository(path)?;
+    repo.edit_reference(RefEdit {
+        change: Change::Update {
+            log: LogChange::default(),
+            expected: PreviousValue::Any,
+            new: Target::Symbolic(head),
+        },
+        name: "HEAD".try_into().with_repository(path)?,
+        deref: false,
+    })
+    .with_repository(path)?;
+
+    Ok(())
+}
+
+/// Commits every file of the repository in `path` which is not ignored,
+/// on top of the current branch.
+///
+/// Without an `author`, the identity configured in git is used.
+pub fn git_commit(path: &Path, message: &str, author: Option<&GitAuthor>) -> Result<()> {
+    let repo = gix::open(path).with_repository(path)?;
+
+    let mut files = Vec::new();
+    {
+        let index = repo.index_or_empty().with_repository(path)?;
+        let mut excludes = repo
+            .excludes(&index, None, Source::default())
+            .with_repository(path)?;
+        collect_files(path, Path::new(""), &mut excludes, &mut files)?;
+    }
+
+    let mut tree = repo
+        .edit_tree(ObjectId::empty_tree(repo.object_hash()))
+        .with_repository(path)?;
+    for relative in &files {
+        let full = path.join(relative);
+        let metadata = symlink_metadata(&full).with_path(&full)?;
+        let (kind, id) = if metadata.is_symlink() {
+            let target = read_link(&full).with_path(&full)?;
+            let target = gix::path::into_bstr(target);
+            let id = repo.write_blob(target.as_bytes()).with_repository(path)?;
+            (EntryKind::Link, id)
+        } else {
+            let kind = if is_executable(&metadata) {
+                EntryKind::BlobExecutable
+            } else {
+                EntryKind::Blob
+            };
+            let id = repo
+                .write_blob(read(&full).with_path(&full)?)
+                .with_repository(path)?;
+            (kind, id)
+        };
+        let relative = gix::path::to_unix_separators_on_windows(gix::path::into_bstr(relative));
+        tree.upsert(relative.as_bstr(), kind, id)
+            .with_repository(path)?;
+    }
+    let tree = tree.write().with_repository(path)?.detach();
+
+    let signature = match author {
+        Some(author) => gix::actor::Signature {
+            name: author.name.as_str().into(),
+            email: author.email.as_str().into(),
+            time: gix::date::Time::now_local_or_utc(),
+        },
+        None => repo
+            .author()
+            .ok_or(Error::MissingAuthor)?
+            .with_repository(path)?
+            .to_owned()
+            .with_repository(path)?,
+    };
+    let mut time = gix::date::parse::TimeBuf::default();
+    let signature = signature.to_ref(&mut time);
+
+    let parents = repo.head_id().ok().map(|id| id.detach());
+    let commit = repo
+        .commit_as(signature, signature, "HEAD", message, tree, parents)
+        .with_repository(path)?;
+    debug!("Committed {} files as {commit}", files.len());
+
+    // Keeps the index in sync with the commit, so the files are not
+    // reported as changed
+    let mut index = repo.index_from_tree(&tree).with_repository(path)?;
+    index.write(Default::default()).with_repository(path)?;
+
+    Ok(())
+}
+
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    excludes: &mut gix::AttributeStack<'_>,
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let full = root.join(dir);
+    let mut entries = read_dir(&full)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.file_name()))
+                .collect::<std::io::Result<Vec<_>>>()
+        })
+        .with_path(&full)?;
+    entries.sort();
+
+    for name in entries {
+        if dir.as_os_str().is_empty() && name == ".git" {
+            continue;
+        }
+        let relative = dir.join(&name);
+        let metadata = symlink_metadata(root.join(&relative)).with_path(root.join(&relative))?;
+        let mode = metadata.is_dir().then_some(Mode::DIR);
+        if excludes
+            .at_path(&relative, mode)
+            .with_repository(root)?
+            .is_excluded()
+        {
+            continue;
+        }
+        if metadata.is_dir() {
+            collect_files(root, &relative, excludes, files)?;
+        } else {
+            files.push(relative);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir, write};
+
+    use super::*;
+
+    fn author() -> GitAuthor {
+        GitAuthor {
+            name: "Jane Doe".to_owned(),
+            email: "jane@example.com".to_owned(),
+        }
+    }
+
+    #[test]
+    fn branch_is_the_requested_one() {
+        let dir = tempfile::tempdir().unwrap();
+
+        git_init(dir.path(), "trunk").unwrap();
+
+        let repo = gix::open(dir.path()).unwrap();
+        assert_eq!(
+            repo.head_name().unwrap().unwrap().as_bstr(),
+            "refs/heads/trunk"
+        );
+    }
+
+    #[test]
+    fn commit_skips_ignored_files_and_keeps_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path().join(".gitignore"), "build/\n*.log\n").unwrap();
+        write(dir.path().join("README.md"), "# Project\n").unwrap();
+        write(dir.path().join("debug.log"), "").unwrap();
+        create_dir(dir.path().join("build")).unwrap();
+        write(dir.path().join("build/output"), "").unwrap();
+        let script = dir.path().join("run.sh");
+        write(&script, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        git_init(dir.path(), "main").unwrap();
+        git_commit(dir.path(), "Initial commit", Some(&author())).unwrap();
+
+        let repo = gix::open(dir.path()).unwrap();
+        let commit = repo.head_commit().unwrap();
+        assert_eq!(commit.message_raw_sloppy(), "Initial commit");
+        let tree = commit.tree().unwrap();
+        let entries: Vec<(String, bool)> = tree
+            .iter()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                (entry.filename().to_string(), entry.mode().is_executable())
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (".gitignore".to_owned(), false),
+                ("README.md".to_owned(), false),
+                ("run.sh".to_owned(), cfg!(unix)),
+            ]
+        );
+    }
+
+    #[test]
+    fn existing_repository_is_not_initialised() {
+        let dir = tempfile::tempdir().unwrap();
+        git_init(dir.path(), "main").unwrap();
+
+        assert!(matches!(
+            git_init(dir.path(), "main"),
+            Err(Error::RepositoryExists { path }) if path == dir.path()
+        ));
+    }
+}
@@ -0,0 +1,404 @@
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+use crate::manifest::relative_path;
+use crate::{Annotation, CustomLicense, FileAttributes, PlannedFile};
+
+// Tag marking a file which already carries its licensing information
+const LICENSE_TAG: &str = "SPDX-License-Identifier:";
+
+// Files REUSE does not look into, by name without extension
+const IGNORED_FILES: &[&str] = &["LICENSE", "LICENCE", "COPYING"];
+
+// Files REUSE does not look into, by name
+const IGNORED_NAMES: &[&str] = &["REUSE.toml"];
+
+// Directories REUSE does not look into
+const IGNORED_DIRS: &[&str] = &["LICENSES", ".reuse"];
+
+/// How comments are written in a file type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comment {
+    /// Each line starts with the marker.
+    Line(&'static str),
+    /// The lines are enclosed between an opening and a closing marker, each
+    /// one starting with a prefix.
+    Block {
+        start: &'static str,
+        prefix: &'static str,
+        end: &'static str,
+    },
+}
+
+const SLASHES: Comment = Comment::Line("//");
+const HASH: Comment = Comment::Line("#");
+const C_BLOCK: Comment = Comment::Block {
+    start: "/*",
+    prefix: " * ",
+    end: " */",
+};
+const HTML_BLOCK: Comment = Comment::Block {
+    start: "<!--",
+    prefix: "",
+    end: "-->",
+};
+
+impl Comment {
+    /// Returns the comment syntax of a file, from its name or its extension.
+    fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+        match name {
+            "Makefile" | "CMakeLists.txt" | "meson_options.txt" | ".gitignore"
+            | ".gitattributes" | ".dockerignore" | ".editorconfig" => return Some(HASH),
+            // Dockerfiles are often suffixed by their platform
+            _ if name.starts_with("Dockerfile") => return Some(HASH),
+            _ => {}
+        }
+
+        let extension = path.extension().and_then(OsStr::to_str)?;
+        let comment = match extension {
+            "rs" | "java" | "kt" | "cpp" | "cc" | "cxx" | "hpp" | "hh" | "js" | "jsx" | "ts"
+            | "tsx" | "go" | "swift" | "cs" => SLASHES,
+            "c" | "h" | "css" => C_BLOCK,
+            "py" | "yml" | "yaml" | "toml" | "sh" | "bash" | "build" | "cfg" | "rb" | "pl" => HASH,
+            "md" | "xml" | "html" | "htm" | "svg" => HTML_BLOCK,
+            _ => return None,
+        };
+
+        Some(comment)
+    }
+
+    /// Writes `lines` as a comment.
+    fn wrap(&self, lines: &[String]) -> String {
+        match self {
+            Comment::Line(marker) => lines
+                .iter()
+                .map(|line| format!("{marker} {line}").trim_end().to_owned())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Comment::Block { start, prefix, end } => {
+                let mut comment = vec![start.to_string()];
+                comment.extend(
+                    lines
+                        .iter()
+                        .map(|line| format!("{prefix}{line}").trim_end().to_owned()),
+                );
+                comment.push(end.to_string());
+                comment.join("\n")
+            }
+        }
+    }
+}
+
+/// The REUSE information of a generated file, followed by the notices of
+/// the custom licenses found in `license`.
+fn header_lines(copyright: &str, license: &str, custom: &[CustomLicense]) -> Vec<String> {
+    let mut lines = vec![
+        format!("SPDX-FileCopyrightText: {copyright}"),
+        String::new(),
+        format!("{LICENSE_TAG} {license}"),
+    ];
+
+    let terms = license.split(|c: char| c.is_whitespace() || c == '(' || c == ')');
+    for license in custom {
+        if let Some(header) = license.header.as_deref() {
+            if terms.clone().any(|term| term == license.id) {
+                lines.push(String::new());
+                lines.extend(header.trim_end().lines().map(str::to_owned));
+            }
+        }
+    }
+
+    lines
+}
+
+/// Returns the REUSE information of the file at `path` as a comment, if its
+/// type admits one.
+pub(crate) fn comment_header(
+    path: &Path,
+    copyright: &str,
+    license: &str,
+    custom: &[CustomLicense],
+) -> Option<String> {
+    Comment::of(path).map(|comment| comment.wrap(&header_lines(copyright, license, custom)))
+}
+
+/// Returns whether REUSE skips the file at `path`.
+fn is_ignored(path: &Path) -> bool {
+    let ignored_dir = path.components().any(|component| match component {
+        Component::Normal(dir) => IGNORED_DIRS.iter().any(|ignored| dir == *ignored),
+        _ => false,
+    });
+    let ignored_file = path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .is_some_and(|stem| IGNORED_FILES.contains(&stem))
+        || path
+            .file_name()
+            .and_then(OsStr::to_str)
+            .is_some_and(|name| IGNORED_NAMES.contains(&name));
+
+    ignored_dir || ignored_file
+}
+
+/// Inserts `header` at the top of `content`, after the lines which must stay
+/// first, such as a shebang or an XML declaration.
+pub(crate) fn prepend(header: &str, content: &str) -> String {
+    let first_line = content.lines().next().unwrap_or_default();
+    let keep_first = (first_line.starts_with("#!") && !first_line.starts_with("#!["))
+        || first_line.starts_with("<?xml");
+
+    match content.split_once('\n') {
+        // The blank line after the first one is kept
+        Some((first, rest)) if keep_first && rest.starts_with('\n') => {
+            format!("{first}\n{header}\n{rest}")
+        }
+        Some((first, rest)) if keep_first => format!("{first}\n{header}\n\n{rest}"),
+        _ if keep_first => format!("{content}\n{header}\n"),
+        _ => format!("{header}\n\n{content}"),
+    }
+}
+
+/// Adds the copyright and license of the project to every file, as a comment
+/// when the file type admits one and as a `.license` file otherwise.
+///
+/// The files matching an annotation get its copyright and license instead,
+/// the last matching annotation taking priority. The notices of the `custom`
+/// licenses a file is released under follow its REUSE information. The files
+/// REUSE ignores and the ones which already declare a license are left
+/// untouched.
+pub(crate) fn add_headers(
+    files: &mut Vec<PlannedFile>,
+    root: &Path,
+    copyright: &str,
+    license: &str,
+    annotations: &[Annotation],
+    custom: &[CustomLicense],
+) {
+    let mut sidecars = Vec::new();
+
+    for file in files.iter_mut() {
+        let relative = file.path.strip_prefix(root).unwrap_or(&file.path);
+        if is_ignored(relative) || file.content.contains(LICENSE_TAG) {
+            continue;
+        }
+
+        let relative = relative_path(&file.path, root);
+        let (copyright, license) = match annotations
+            .iter()
+            .rev()
+            .find(|annotation| annotation.matches(&relative))
+        {
+            Some(annotation) => (annotation.copyright_or(copyright), &*annotation.license),
+            None => (copyright, license),
+        };
+
+        match comment_header(&file.path, copyright, license, custom) {
+            Some(header) => file.content = prepend(&header, &file.content),
+            None => {
+                let mut path = file.path.clone().into_os_string();
+                path.push(".license");
+                sidecars.push(PlannedFile {
+                    path: PathBuf::from(path),
+                    template: file.template.clone(),
+                    content: format!("{}\n", header_lines(copyright, license, custom).join("\n")),
+                    attributes: FileAttributes::default(),
+                    component: file.component.clone(),
+                });
+            }
+        }
+    }
+
+    files.extend(sidecars);
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COPYRIGHT: &str = "2026 Jane Doe";
+
+    fn file(path: &str, content: &str) -> PlannedFile {
+        PlannedFile {
+            path: Path::new("/project").join(path),
+            template: String::new(),
+            content: content.to_owned(),
+            attributes: FileAttributes::default(),
+            component: None,
+        }
+    }
+
+    fn headed(files: &[(&str, &str)], annotations: &[Annotation]) -> Vec<PlannedFile> {
+        let mut files = files
+            .iter()
+            .map(|(path, content)| file(path, content))
+            .collect();
+        add_headers(
+            &mut files,
+            Path::new("/project"),
+            COPYRIGHT,
+            "MIT",
+            annotations,
+            &[],
+        );
+        files
+    }
+
+    #[test]
+    fn comment_syntax_per_file_type() {
+        let comment = |path: &str| Comment::of(Path::new(path));
+
+        assert_eq!(comment("src/main.rs"), Some(SLASHES));
+        assert_eq!(comment("Main.java"), Some(SLASHES));
+        assert_eq!(comment("lib.c"), Some(C_BLOCK));
+        assert_eq!(comment("pyproject.toml"), Some(HASH));
+        assert_eq!(comment("meson.build"), Some(HASH));
+        assert_eq!(comment("Makefile"), Some(HASH));
+        assert_eq!(comment("Dockerfile-amd64"), Some(HASH));
+        assert_eq!(comment(".gitignore"), Some(HASH));
+        assert_eq!(comment("README.md"), Some(HTML_BLOCK));
+        assert_eq!(comment("pom.xml"), Some(HTML_BLOCK));
+        assert_eq!(comment("data.json"), None);
+        assert_eq!(comment("LICENSE"), None);
+    }
+
+    #[test]
+    fn comments_are_wrapped() {
+        let lines = header_lines(COPYRIGHT, "MIT", &[]);
+
+        assert_eq!(
+            HASH.wrap(&lines),
+            "# SPDX-FileCopyrightText: 2026 Jane Doe\n#\n# SPDX-License-Identifier: MIT"
+        );
+        assert_eq!(
+            C_BLOCK.wrap(&lines),
+            "/*\n * SPDX-FileCopyrightText: 2026 Jane Doe\n *\n * SPDX-License-Identifier: MIT\n */"
+        );
+        assert_eq!(
+            HTML_BLOCK.wrap(&lines),
+            "<!--\nSPDX-FileCopyrightText: 2026 Jane Doe\n\nSPDX-License-Identifier: MIT\n-->"
+        );
+    }
+
+    #[test]
+    fn header_comes_first() {
+        assert_eq!(prepend("# header", "content\n"), "# header\n\ncontent\n");
+        // An inner attribute is not a shebang
+        assert_eq!(
+            prepend("// header", "#![no_std]\n"),
+            "// header\n\n#![no_std]\n"
+        );
+    }
+
+    #[test]
+    fn shebang_and_xml_declaration_stay_first() {
+        assert_eq!(
+            prepend("# header", "#!/bin/sh\necho\n"),
+            "#!/bin/sh\n# header\n\necho\n"
+        );
+        assert_eq!(
+            prepend("# header", "#!/bin/sh\n\necho\n"),
+            "#!/bin/sh\n# header\n\necho\n"
+        );
+        assert_eq!(prepend("# header", "#!/bin/sh"), "#!/bin/sh\n# header\n");
+        assert_eq!(
+            prepend("<!-- header -->", "<?xml version=\"1.0\"?>\n<project/>"),
+            "<?xml version=\"1.0\"?>\n<!-- header -->\n\n<project/>"
+        );
+    }
+
+    #[test]
+    fn files_without_comments_get_a_sidecar() {
+        let files = headed(&[("data.json", "{}")], &[]);
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].content, "{}");
+        assert_eq!(files[1].path, Path::new("/project/data.json.license"));
+        assert_eq!(
+            files[1].content,
+            "SPDX-FileCopyrightText: 2026 Jane Doe\n\nSPDX-License-Identifier: MIT\n"
+        );
+    }
+
+    #[test]
+    fn ignored_and_licensed_files_are_untouched() {
+        let licensed = "# SPDX-License-Identifier: MIT\n";
+        let files = headed(
+            &[
+                ("LICENSE.md", "text"),
+                ("LICENSES/MIT.txt", "text"),
+                ("REUSE.toml", "version = 1"),
+                ("licensed.py", licensed),
+            ],
+            &[],
+        );
+
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[0].content, "text");
+        assert_eq!(files[1].content, "text");
+        assert_eq!(files[2].content, "version = 1");
+        assert_eq!(files[3].content, licensed);
+    }
+
+    #[test]
+    fn custom_license_notices_follow_the_tags() {
+        let custom = |id: &str, header: &str| CustomLicense {
+            id: id.to_owned(),
+            name: String::new(),
+            text: PathBuf::new(),
+            header: Some(header.to_owned()),
+        };
+        let custom = [
+            custom("LicenseRef-ACME", "Property of ACME.\n"),
+            custom("LicenseRef-ACME-2", "Property of ACME 2."),
+        ];
+
+        assert_eq!(
+            comment_header(
+                Path::new("main.py"),
+                COPYRIGHT,
+                "(LicenseRef-ACME OR MIT)",
+                &custom
+            )
+            .unwrap(),
+            "# SPDX-FileCopyrightText: 2026 Jane Doe\n#\n\
+             # SPDX-License-Identifier: (LicenseRef-ACME OR MIT)\n#\n\
+             # Property of ACME."
+        );
+        assert!(
+            !comment_header(Path::new("main.py"), COPYRIGHT, "MIT", &custom)
+                .unwrap()
+                .contains("Property")
+        );
+    }
+
+    #[test]
+    fn last_matching_annotation_wins() {
+        let annotation = |path: &str, license: &str, copyright: &str| Annotation {
+            paths: vec![path.to_owned()],
+            license: license.to_owned(),
+            copyright: copyright.to_owned(),
+        };
+        let files = headed(
+            &[("docs/a.md", "a"), ("docs/b.yml", "b")],
+            &[
+                annotation("docs/**", "CC-BY-4.0", "2026 Writer"),
+                annotation("**.yml", "CC0-1.0", ""),
+            ],
+        );
+
+        assert!(files[0]
+            .content
+            .contains("SPDX-FileCopyrightText: 2026 Writer"));
+        assert!(files[0]
+            .content
+            .contains("SPDX-License-Identifier: CC-BY-4.0"));
+        assert!(files[1]
+            .content
+            .contains("SPDX-FileCopyrightText: 2026 Jane Doe"));
+        assert!(files[1]
+            .content
+            .contains("SPDX-License-Identifier: CC0-1.0"));
+    }
+}
@@ -0,0 +1,77 @@
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::process::{Command, Stdio};
+
+use tracing::{info, warn};
+
+use crate::{Error, Plan, Result};
+
+fn stream(output: impl Read) {
+    for line in BufReader::new(output).lines().map_while(Result::ok) {
+        info!("{line}");
+    }
+}
+
+impl Plan {
+    /// Runs the hooks of the plan, one after the other, in the project root.
+    ///
+    /// The output of the commands is streamed through `tracing`. A command
+    /// which cannot be found is skipped with a warning, a command which
+    /// fails stops the execution of the following ones.
+    pub fn run_hooks(&self) -> Result<()> {
+        for hook in &self.hooks {
+            let words = shell_words::split(hook).map_err(|e| Error::Hook {
+                hook: hook.to_owned(),
+                source: e.into(),
+            })?;
+            let Some((program, args)) = words.split_first() else {
+                continue;
+            };
+
+            info!("Running {hook}");
+            let child = Command::new(program)
+                .args(args)
+                .current_dir(&self.root)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn();
+            let mut child = match child {
+                Ok(child) => child,
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    warn!("Skipping {hook}: {program} not found");
+                    continue;
+                }
+                Err(e) => {
+                    return Err(Error::Hook {
+                        hook: hook.to_owned(),
+                        source: e.into(),
+                    })
+                }
+            };
+
+            let stderr = child
+                .stderr
+                .take()
+                .map(|stderr| std::thread::spawn(|| stream(stderr)));
+            if let Some(stdout) = child.stdout.take() {
+                stream(stdout);
+            }
+            if let Some(stderr) = stderr {
+                let _ = stderr.join();
+            }
+
+            let status = child.wait().map_err(|e| Error::Hook {
+                hook: hook.to_owned(),
+                source: e.into(),
+            })?;
+            if !status.success() {
+                return Err(Error::HookFailed {
+                    hook: hook.to_owned(),
+                    status,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,225 +1,1251 @@
 pub mod toolchain;
 pub use toolchain::*;
 
+pub mod pack;
+
+mod check;
+mod error;
 mod filters;
+mod git;
+mod headers;
+mod hooks;
+mod licensing;
+mod manifest;
+mod naming;
+mod registry;
+mod reuse;
+mod sink;
+mod staging;
+mod upgrade;
+
+pub use check::{check, Drift};
+pub use error::{BoxError, Error, Result};
+pub use git::{git_commit, git_identity, git_init, GitAuthor};
+pub use licensing::{CustomLicense, Licensing};
+pub use manifest::{hash, Manifest, MANIFEST_FILE};
+pub use registry::Registry;
+pub use reuse::{migrate_dep5, Annotation, ReuseFormat};
+pub use sink::{FsSink, MemorySink, Sink, TarGzSink, ZipSink};
+pub use upgrade::{upgrade, MergeStyle, Upgrade};
 
-use std::collections::HashMap;
-use std::fs::{create_dir_all, write};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::fs::{read_dir, read_to_string};
 use std::path::{Path, PathBuf};
 
-use anyhow::{bail, Result};
 use minijinja::value::Value;
 use minijinja::Environment;
+use serde::{Deserialize, Serialize};
 use tracing::debug;
 
+use error::{WithPath, WithTemplate};
 use filters::*;
 
+// Licenses whose text starts with a copyright line to be filled in, the
+// other ones are kept verbatim
+const FILLED_LICENSES: &[&str] = &[
+    "BSD-1-Clause",
+    "BSD-2-Clause",
+    "BSD-2-Clause-Views",
+    "BSD-3-Clause",
+    "BSD-4-Clause",
+    "ECL-1.0",
+    "ISC",
+    "MIT",
+    "X11-distribute-modifications-variant",
+];
+
+// Placeholders of the license texts, replaced by the copyright year
+const YEAR_PLACEHOLDERS: &[&str] = &["<year>", "[year]", "<yyyy>", "[yyyy]", "[xxxx]"];
+
+// Placeholders of the license texts, replaced by the copyright holder
+const HOLDER_PLACEHOLDERS: &[&str] = &[
+    "<copyright holders>",
+    "<owner>",
+    "<name of author>",
+    "[fullname]",
+    "[name of copyright owner]",
+    "[Owner Organization]",
+];
+
+// Components of the built-in toolchains, not every toolchain has all of them
+const COMPONENTS: &[&str] = &[
+    "ci", "docker", "fuzz", "deploy", "readme", "license", "reuse",
+];
+
 static REUSE_TEMPLATE: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/", "dep5"));
 
-/// Used to create a CI configuration for a project.
-pub trait CreateCi {
-    /// Creates a new CI configuration for a project.
-    fn create_ci(
-        &self,
-        project_name: &str,
-        project_path: &Path,
-        license: &str,
-        github_branch: &str,
-    ) -> Result<()>;
+static REUSE_TOML_TEMPLATE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/templates/",
+    "REUSE.toml"
+));
+
+/// Settings customizing a generation.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Directories containing user templates, from the highest priority to
+    /// the lowest one.
+    ///
+    /// Each directory holds a subdirectory per toolchain, whose files replace
+    /// the built-in templates with the same file name.
+    pub templates_dirs: Vec<PathBuf>,
+    /// Directories containing template packs, from the highest priority to
+    /// the lowest one.
+    pub packs_dirs: Vec<PathBuf>,
+    /// Extra variables made available to the templates.
+    pub vars: BTreeMap<String, String>,
+    /// Commands run after the default hooks of the toolchain.
+    pub hooks: Vec<String>,
+    /// Do not run the default hooks of the toolchain.
+    pub skip_default_hooks: bool,
+    /// Components to be generated, all of them when empty.
+    pub only: Vec<String>,
+    /// Components not to be generated.
+    pub without: Vec<String>,
+    /// Template sources rendered in place of any other one, keyed by
+    /// template name.
+    pub template_sources: BTreeMap<String, String>,
+    /// Licenses outside of the SPDX list the license expression can refer
+    /// to.
+    pub licenses: Vec<CustomLicense>,
+    /// Format of the REUSE metadata.
+    pub reuse_format: ReuseFormat,
+    /// Licensing information of some paths, in place of the one of the
+    /// project.
+    pub annotations: Vec<Annotation>,
 }
 
-/// Used to create a new project.
-pub trait CreateProject {
-    /// Creates a new project.
-    fn create_project(
-        &self,
-        project_name: &str,
-        project_path: &Path,
-        license: &str,
-        github_branch: &str,
-    ) -> Result<()>;
+impl Options {
+    /// Returns whether the files of `component` are generated.
+    pub fn includes(&self, component: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|only| only == component))
+            && !self.without.iter().any(|without| without == component)
+    }
+}
+
+/// A file produced by a generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    /// Path of the file.
+    pub path: PathBuf,
+    /// Name of the template the file is rendered from.
+    pub template: String,
+    /// Rendered content of the file.
+    pub content: String,
+    /// Attributes applied to the file once written.
+    pub attributes: FileAttributes,
+    /// Component the file belongs to, if any.
+    pub component: Option<String>,
+}
+
+/// The directories and files a generation produces, computed in memory.
+///
+/// Directories and files are sorted by path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    /// Root of the project.
+    pub root: PathBuf,
+    /// Directories to be created.
+    pub dirs: Vec<PathBuf>,
+    /// Files to be written.
+    pub files: Vec<PlannedFile>,
+    /// Existing files to be removed once the others are written.
+    pub removed: Vec<PathBuf>,
+    /// Commands to be run in the project root once the files are written.
+    pub hooks: Vec<String>,
+}
+
+/// What to do when a file of a plan already exists on disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Do not write anything and report every existing file
+    #[default]
+    Abort,
+    /// Keep the existing files and write only the missing ones
+    Skip,
+    /// Replace the existing files
+    Overwrite,
+    /// Rename the existing files to `*.orig` before writing, aborting when
+    /// a `*.orig` file already exists
+    Backup,
+}
+
+impl Plan {
+    /// Returns the files of the plan which already exist on disk.
+    pub fn conflicts(&self) -> Vec<&Path> {
+        self.files
+            .iter()
+            .map(|file| file.path.as_path())
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// Creates the directories and writes the files of the plan,
+    /// resolving the already existing files according to `policy`.
+    ///
+    /// The files are moved into place only once all of them have been
+    /// written, on failure the existing files are left untouched.
+    pub fn write(&self, policy: ConflictPolicy) -> Result<()> {
+        let conflicts = self.conflicts();
+
+        if policy == ConflictPolicy::Abort && !conflicts.is_empty() {
+            return Err(Error::Conflicts {
+                paths: conflicts.iter().map(|path| path.to_path_buf()).collect(),
+            });
+        }
+
+        // An earlier backup is never replaced
+        if policy == ConflictPolicy::Backup {
+            let backups: Vec<PathBuf> = conflicts
+                .iter()
+                .map(|path| staging::backup_path(path))
+                .filter(|backup| backup.exists())
+                .collect();
+            if !backups.is_empty() {
+                return Err(Error::Conflicts { paths: backups });
+            }
+        }
+
+        // The files kept as they are were not generated, the manifest does
+        // not record them
+        if policy == ConflictPolicy::Skip && !conflicts.is_empty() {
+            let skipped: Vec<String> = conflicts
+                .iter()
+                .map(|path| manifest::relative_path(path, &self.root))
+                .collect();
+            let manifest_path = self.root.join(MANIFEST_FILE);
+            let mut plan = self.clone();
+            for file in &mut plan.files {
+                if file.path == manifest_path {
+                    file.content = Manifest::without_files(&file.content, &skipped)?;
+                }
+            }
+            return staging::write(&plan, policy, &conflicts);
+        }
+
+        staging::write(self, policy, &conflicts)
+    }
+
+    /// Creates the directories and writes the files of the plan into `sink`.
+    ///
+    /// The files to be removed are not recorded.
+    pub fn write_to(&self, sink: &mut dyn Sink) -> Result<()> {
+        for dir in &self.dirs {
+            sink.create_dir(dir)?;
+        }
+
+        for file in &self.files {
+            sink.write_file(&file.path, file.content.as_bytes(), file.attributes)?;
+        }
+
+        Ok(())
+    }
+}
+
+// A file of the project to be rendered
+struct ProjectFile {
+    template: String,
+    attributes: FileAttributes,
+    component: Option<String>,
+}
+
+impl ProjectFile {
+    fn new(template: &str, component: &str) -> Self {
+        Self {
+            template: template.to_owned(),
+            attributes: FileAttributes::default(),
+            component: Some(component.to_owned()),
+        }
+    }
 }
 
 struct SifisTemplate {
-    context: HashMap<&'static str, Value>,
-    files: HashMap<PathBuf, &'static str>,
+    toolchain: String,
+    inputs: BTreeMap<String, String>,
+    context: HashMap<String, Value>,
+    files: HashMap<PathBuf, ProjectFile>,
     dirs: Vec<PathBuf>,
+    hooks: Vec<String>,
     env: Environment<'static>,
+    // Texts written as they are, such as the licenses, by template name
+    verbatim: HashMap<String, String>,
+    authorship: Authorship,
+}
+
+/// Who holds the copyright of the generated files, and since when.
+#[derive(Debug, Clone)]
+struct Authorship {
+    author: String,
+    email: String,
+    holder: String,
+    year: String,
+}
+
+impl Authorship {
+    /// Resolves the authorship of a request, falling back to the authors of
+    /// the project and to the current year.
+    fn new(request: &GenerationRequest, project_name: &str) -> Self {
+        let holder = if !request.copyright_holder.is_empty() {
+            request.copyright_holder.clone()
+        } else if !request.author.is_empty() {
+            request.author.clone()
+        } else {
+            format!("The {project_name} Authors")
+        };
+        let year = if request.year.is_empty() {
+            jiff::Zoned::now().year().to_string()
+        } else {
+            request.year.clone()
+        };
+
+        Self {
+            author: request.author.clone(),
+            email: request.email.clone(),
+            holder,
+            year,
+        }
+    }
+
+    /// Notice of the `SPDX-FileCopyrightText` tags, with the email when the
+    /// author holds the copyright.
+    fn copyright(&self) -> String {
+        if self.holder == self.author && !self.email.is_empty() {
+            format!("{} {} <{}>", self.year, self.holder, self.email)
+        } else {
+            format!("{} {}", self.year, self.holder)
+        }
+    }
+
+    /// Adds the values to the inputs recorded in the manifest, so that a
+    /// replay resolves the same authorship.
+    fn record(&self, inputs: &mut BTreeMap<String, String>) {
+        for (key, value) in [
+            ("author", &self.author),
+            ("email", &self.email),
+            ("copyright_holder", &self.holder),
+            ("year", &self.year),
+        ] {
+            if !value.is_empty() {
+                inputs.insert(key.to_owned(), value.to_owned());
+            }
+        }
+    }
+
+    /// Adds the template variables.
+    fn add_context(&self, context: &mut HashMap<String, Value>) {
+        context.insert("author".to_owned(), Value::from(self.author.as_str()));
+        context.insert("email".to_owned(), Value::from(self.email.as_str()));
+        context.insert(
+            "copyright_holder".to_owned(),
+            Value::from(self.holder.as_str()),
+        );
+        context.insert("year".to_owned(), Value::from(self.year.as_str()));
+        context.insert("copyright".to_owned(), Value::from(self.copyright()));
+    }
+
+    /// Replaces the year and holder placeholders of the copyright lines of
+    /// a license text, as long as the license is meant to be filled in.
+    fn fill(&self, id: &str, text: &str) -> String {
+        if !FILLED_LICENSES.contains(&id) {
+            return text.to_owned();
+        }
+
+        let fill_line = |line: &str| {
+            if !line.trim_start().starts_with("Copyright") {
+                return line.to_owned();
+            }
+            let line = YEAR_PLACEHOLDERS
+                .iter()
+                .fold(line.to_owned(), |line, placeholder| {
+                    line.replace(placeholder, &self.year)
+                });
+            HOLDER_PLACEHOLDERS.iter().fold(line, |line, placeholder| {
+                line.replace(placeholder, &self.holder)
+            })
+        };
+        // The line breaks of the text are kept as they are
+        text.split_inclusive('\n').map(fill_line).collect()
+    }
 }
 
 impl SifisTemplate {
-    fn render(self) -> Result<()> {
-        //let mut env = Environment::new();
+    fn render(self) -> Result<Plan> {
         let SifisTemplate {
             context,
             files,
-            dirs,
+            mut dirs,
             mut env,
+            verbatim,
+            ..
         } = self;
 
-        // Create dirs
-        for dir in dirs {
-            debug!("Creating {}", dir.display());
-            create_dir_all(dir)?
-        }
+        dirs.sort();
+        dirs.dedup();
 
         env.add_filter("comment_license", comment_license);
         env.add_filter("hypens_to_underscores", hypens_to_underscores);
+        env.add_filter("toml_string", toml_string);
+        env.add_filter("xml_escape", xml_escape);
 
         // Fill in templates
-        for (path, template_name) in files {
-            debug!("Creating {}", path.display());
-            let template = env.get_template(template_name)?;
-            let filled_template = template.render(&context)?;
-            write(path, filled_template)?;
-        }
+        let mut files = files
+            .into_iter()
+            .map(|(path, file)| {
+                let content = match verbatim.get(&file.template) {
+                    Some(text) => text.to_owned(),
+                    None => env
+                        .get_template(&file.template)
+                        .and_then(|template| template.render(&context))
+                        .with_target(&file.template, &path)?,
+                };
+                Ok(PlannedFile {
+                    path,
+                    template: file.template,
+                    content,
+                    attributes: file.attributes,
+                    component: file.component,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
 
-        Ok(())
+        Ok(Plan {
+            dirs,
+            files,
+            ..Default::default()
+        })
     }
 
     fn add_license(
         &mut self,
-        license: &dyn license::License,
+        licensing: &Licensing,
+        annotations: &[Licensing],
         project_path: &Path,
-    ) -> anyhow::Result<()> {
-        let id = license.id();
-        let header = license.header();
-
-        // Adds LICENSE directory and license file
+    ) -> Result<()> {
+        // Adds LICENSES directory and a file for each license and exception,
+        // including the ones of the annotated paths
         let license_path = project_path.join("LICENSES");
-        self.files
-            .insert(license_path.join(format!("{}.txt", id)), "build.license");
+        let annotated = annotations.iter().flat_map(Licensing::texts);
+        for (id, text) in licensing.texts().chain(annotated) {
+            let path = license_path.join(format!("{id}.txt"));
+            if self.files.contains_key(&path) {
+                continue;
+            }
+            let name = format!("build.license.{id}");
+            self.files.insert(path, ProjectFile::new(&name, "license"));
+            self.verbatim.insert(name, self.authorship.fill(id, text));
+        }
         self.dirs.push(license_path);
 
-        let text_without_blank: Vec<&str> = license
-            .text()
-            .lines()
-            .skip(2) // Skip a blank line and license id
-            .filter(|x| !x.is_empty())
-            .collect();
+        // The texts of the project ones after each other, for the templates
+        // showing them
+        let text = licensing
+            .texts()
+            .map(|(id, text)| self.authorship.fill(id, text).trim_end().to_owned())
+            .collect::<Vec<_>>()
+            .join("\n\n");
 
         let mut license_ctx = HashMap::new();
 
-        license_ctx.insert("header", Value::from_serializable(&header));
-        license_ctx.insert("text", Value::from_serializable(&text_without_blank));
-        license_ctx.insert("id", Value::from_serializable(&id));
+        license_ctx.insert("id", Value::from_serialize(licensing.expression()));
+
+        self.context
+            .insert("license".to_owned(), Value::from_serialize(&license_ctx));
+        let licenses: Vec<_> = licensing
+            .licenses()
+            .map(|(id, name, url)| {
+                let mut license_ctx = HashMap::new();
+                license_ctx.insert("id", Value::from(id));
+                license_ctx.insert("name", Value::from(name));
+                license_ctx.insert("url", Value::from_serialize(url));
+                license_ctx
+            })
+            .collect();
+        self.context
+            .insert("licenses".to_owned(), Value::from_serialize(&licenses));
+        self.context.insert(
+            "license_ids".to_owned(),
+            Value::from_serialize(licensing.license_ids()),
+        );
+        self.context.insert(
+            "exception_ids".to_owned(),
+            Value::from_serialize(licensing.exception_ids()),
+        );
+
+        self.verbatim
+            .insert("build.license".to_owned(), format!("{text}\n"));
+
+        Ok(())
+    }
+
+    fn select_components(&mut self, options: &Options) -> Result<Vec<String>> {
+        let mut available: Vec<String> = self
+            .files
+            .values()
+            .filter_map(|file| file.component.clone())
+            .collect();
+        available.sort();
+        available.dedup();
+
+        for name in options.only.iter().chain(&options.without) {
+            if !available.contains(name) && !COMPONENTS.contains(&name.as_str()) {
+                return Err(Error::UnknownComponent {
+                    toolchain: self.toolchain.to_owned(),
+                    name: name.to_owned(),
+                    available,
+                });
+            }
+        }
 
+        let before: Vec<PathBuf> = self.files.keys().cloned().collect();
+        self.files.retain(|_, file| {
+            file.component
+                .as_ref()
+                .is_none_or(|component| options.includes(component))
+        });
+        let files = &self.files;
+        self.dirs.retain(|dir| {
+            files.keys().any(|path| path.starts_with(dir))
+                || !before.iter().any(|path| path.starts_with(dir))
+        });
+
+        available.retain(|component| options.includes(component));
         self.context
-            .insert("license", Value::from_serializable(&license_ctx));
+            .insert("components".to_owned(), Value::from_serialize(&available));
+
+        Ok(available)
+    }
 
-        self.env.add_template("build.license", license.text())?;
+    fn add_vars(&mut self, vars: &BTreeMap<String, String>) -> Result<()> {
+        for (key, value) in vars {
+            if self.context.contains_key(key) {
+                return Err(Error::VariableConflict {
+                    toolchain: self.toolchain.to_owned(),
+                    variable: key.to_owned(),
+                });
+            }
+            self.context
+                .insert(key.to_owned(), Value::from(value.as_str()));
+        }
 
         Ok(())
     }
 
     fn add_reuse(
         &mut self,
-        license: &dyn license::License,
+        licensing: &Licensing,
+        options: &Options,
         project_path: &Path,
-    ) -> anyhow::Result<()> {
-        // Adds .reuse directory and dep5 file
-        let reuse_path = project_path.join(".reuse");
-        self.files.insert(reuse_path.join("dep5"), "dep5.reuse");
-        self.dirs.push(reuse_path);
+    ) -> Result<()> {
+        let (template, source) = match options.reuse_format {
+            ReuseFormat::Toml => ("toml.reuse", REUSE_TOML_TEMPLATE),
+            ReuseFormat::Dep5 => ("dep5.reuse", REUSE_TEMPLATE),
+        };
+        self.files.insert(
+            project_path.join(match options.reuse_format {
+                ReuseFormat::Toml => reuse::REUSE_TOML_FILE,
+                ReuseFormat::Dep5 => reuse::DEP5_FILE,
+            }),
+            ProjectFile::new(template, "reuse"),
+        );
+        if options.reuse_format == ReuseFormat::Dep5 {
+            self.dirs.push(project_path.join(".reuse"));
+        }
 
-        // Gets project name and license header
+        // Gets project name and license expression
         let name = self.context.get("name");
-        let id = license.id();
+        let id = licensing.expression();
+        let copyright = self.authorship.copyright();
+
+        let annotations: Vec<_> = options
+            .annotations
+            .iter()
+            .map(|annotation| {
+                let mut annotation_ctx = HashMap::new();
+                annotation_ctx.insert("paths", Value::from_serialize(&annotation.paths));
+                annotation_ctx.insert("license", Value::from_serialize(&annotation.license));
+                annotation_ctx.insert(
+                    "copyright",
+                    Value::from_serialize(annotation.copyright_or(&copyright)),
+                );
+                annotation_ctx
+            })
+            .collect();
 
         let mut reuse = HashMap::new();
 
-        reuse.insert("name", Value::from_serializable(&name));
-        reuse.insert("id", Value::from_serializable(&id));
+        reuse.insert("name", Value::from_serialize(name));
+        reuse.insert("id", Value::from_serialize(id));
+        reuse.insert("copyright", Value::from_serialize(&copyright));
+        reuse.insert("annotations", Value::from_serialize(&annotations));
 
         self.context
-            .insert("reuse", Value::from_serializable(&reuse));
+            .insert("reuse".to_owned(), Value::from_serialize(&reuse));
 
-        self.env.add_template("dep5.reuse", REUSE_TEMPLATE)?;
+        self.env
+            .add_template(template, source)
+            .with_template(template)?;
 
         Ok(())
     }
 }
 
 /// Build a template
-trait BuildTemplate {
+trait BuildTemplate: Toolchain {
     fn define(
         &self,
         project_path: &Path,
         project_name: &str,
         license: &str,
         github_branch: &str,
+        inputs: &BTreeMap<String, String>,
     ) -> (
-        HashMap<PathBuf, &'static str>,
+        HashMap<PathBuf, TemplateFile>,
         Vec<PathBuf>,
         HashMap<&'static str, Value>,
     );
 
-    fn get_templates() -> &'static [(&'static str, &'static str)];
+    /// Root of the project to be generated
+    fn project_path(&self, project_path: &Path, _project_name: &str) -> PathBuf {
+        project_path.to_path_buf()
+    }
+
+    /// Checks the project name, and the inputs naming the project, against
+    /// the rules of the ecosystem
+    fn check_name(&self, _project_name: &str, _inputs: &BTreeMap<String, String>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Commands run after the generation, unless disabled
+    fn hooks() -> &'static [&'static str] {
+        &[]
+    }
 
     fn build(
         &self,
         project_path: &Path,
         project_name: &str,
         license: &str,
-        github_branch: &str,
-    ) -> SifisTemplate {
-        let (files, dirs, context) =
-            self.define(project_path, project_name, license, github_branch);
-        let env = build_environment(Self::get_templates());
+        request: &GenerationRequest,
+    ) -> Result<SifisTemplate> {
+        let (values, variables) = request.resolve_inputs(self.name(), &self.inputs())?;
+        self.check_name(project_name, &values)?;
+        let (files, dirs, context) = self.define(
+            project_path,
+            project_name,
+            license,
+            &request.branch,
+            &values,
+        );
+        let env = build_environment(
+            self.name(),
+            self.templates(),
+            &request.options.templates_dirs,
+        )?;
 
-        SifisTemplate {
-            context,
-            files,
+        let authorship = Authorship::new(request, project_name);
+        let mut inputs = BTreeMap::from([
+            ("name".to_owned(), project_name.to_owned()),
+            ("license".to_owned(), license.to_owned()),
+            ("branch".to_owned(), request.branch.to_owned()),
+        ]);
+        authorship.record(&mut inputs);
+        inputs.extend(values);
+
+        let mut variables = variables;
+        authorship.add_context(&mut variables);
+
+        Ok(SifisTemplate {
+            toolchain: self.name().to_owned(),
+            inputs,
+            context: variables
+                .into_iter()
+                .chain(
+                    context
+                        .into_iter()
+                        .map(|(key, value)| (key.to_owned(), value)),
+                )
+                .collect(),
+            files: files
+                .into_iter()
+                .map(|(path, file)| {
+                    // The attributes of the template apply unless replaced
+                    let template = self
+                        .templates()
+                        .iter()
+                        .find(|template| template.name == file.name)
+                        .map(|template| template.attributes)
+                        .unwrap_or_default();
+                    let file = ProjectFile {
+                        template: file.name.to_owned(),
+                        attributes: file.attributes.or(template),
+                        component: file.component.map(str::to_owned),
+                    };
+                    (path, file)
+                })
+                .collect(),
             dirs,
+            hooks: Self::hooks().iter().map(|hook| hook.to_string()).collect(),
             env,
-        }
+            verbatim: HashMap::new(),
+            authorship,
+        })
     }
 }
 
-fn build_environment(templates: &'static [(&'static str, &'static str)]) -> Environment<'static> {
+fn build_environment(
+    toolchain: &str,
+    templates: &'static [BuiltinTemplate],
+    templates_dirs: &[PathBuf],
+) -> Result<Environment<'static>> {
     let mut environment = Environment::new();
-    for (name, src) in templates {
+    for template in templates {
         environment
-            .add_template(name, src)
+            .add_template(template.name, template.source)
             .expect("Internal error, built-in template");
     }
 
-    environment
+    let overrides = user_templates(toolchain, templates_dirs, |file| {
+        templates
+            .iter()
+            .find(|template| template.file == file)
+            .map(|template| template.name.to_owned())
+    })?;
+    for (name, source) in overrides {
+        environment
+            .add_template_owned(name.clone(), source)
+            .with_template(&name)?;
+    }
+
+    Ok(environment)
 }
 
-pub(crate) fn define_name<'a>(project_name: &'a str, project_path: &'a Path) -> Result<&'a str> {
-    let project_name = if project_name.is_empty() {
-        if let Some(os_name) = project_path.file_name() {
-            if let Some(name) = os_name.to_str() {
-                name
-            } else {
-                bail!("Impossible to convert the project name into a valid Unicode string");
-            }
-        } else {
-            bail!("Impossible to get the project name");
+/// Reads the user templates of a toolchain, the ones in the lowest
+/// priority directory first.
+///
+/// `lookup` returns the name of the template replaced by a file.
+pub(crate) fn user_templates(
+    toolchain: &str,
+    templates_dirs: &[PathBuf],
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<(String, String)>> {
+    let mut templates = Vec::new();
+
+    for dir in templates_dirs.iter().rev() {
+        let dir = dir.join(toolchain);
+        if !dir.is_dir() {
+            continue;
         }
-    } else {
-        project_name
-    };
+        for entry in read_dir(&dir).with_path(&dir)? {
+            let path = entry.with_path(&dir)?.path();
+            let Some(name) = path.file_name().and_then(OsStr::to_str).and_then(&lookup) else {
+                return Err(Error::UnknownTemplate {
+                    toolchain: toolchain.to_owned(),
+                    path,
+                });
+            };
+            debug!("Using {} as {name}", path.display());
+            let source = read_to_string(&path).with_path(&path)?;
+            templates.push((name, source));
+        }
+    }
 
-    Ok(project_name)
+    Ok(templates)
 }
 
-pub(crate) fn define_license(license: &str) -> Result<&dyn license::License> {
-    let license = license
-        .parse::<&dyn license::License>()
-        .map_err(|_| anyhow::anyhow!("Cannot find License"))?;
-    Ok(license)
+pub(crate) fn define_name<'a>(project_name: &'a str, project_path: &'a Path) -> Result<&'a str> {
+    if !project_name.is_empty() {
+        return Ok(project_name);
+    }
+
+    // The last component of the path, as long as it is valid Unicode
+    project_path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .ok_or_else(|| Error::ProjectName {
+            path: project_path.to_path_buf(),
+        })
+}
+
+pub(crate) fn generate<T: BuildTemplate>(
+    toolchain: &T,
+    request: &GenerationRequest,
+) -> Result<Plan> {
+    let project_name = define_name(&request.name, &request.project_path)?;
+    let licensing = Licensing::parse(&request.license, &request.options.licenses)?;
+    let project_path = toolchain.project_path(&request.project_path, project_name);
+    let template = toolchain.build(&project_path, project_name, licensing.expression(), request)?;
+    compute_template(template, &licensing, &project_path, &request.options)
 }
 
 pub(crate) fn compute_template(
     mut template: SifisTemplate,
-    license: &dyn license::License,
+    licensing: &Licensing,
     project_path: &Path,
-) -> Result<()> {
-    template.add_reuse(license, project_path)?;
-    template.add_license(license, project_path)?;
+    options: &Options,
+) -> Result<Plan> {
+    let annotations = options
+        .annotations
+        .iter()
+        .map(|annotation| Licensing::parse(&annotation.license, &options.licenses))
+        .collect::<Result<Vec<_>>>()?;
+    template.add_reuse(licensing, options, project_path)?;
+    template.add_license(licensing, &annotations, project_path)?;
+    let components = template.select_components(options)?;
+    template.add_vars(&options.vars)?;
+
+    for (name, source) in &options.template_sources {
+        template
+            .env
+            .add_template_owned(name.to_owned(), source.to_owned())
+            .with_template(name)?;
+    }
+
+    // The sources of the templates are recorded, the license texts copied
+    // verbatim come from the SPDX data rather than from a template
+    let mut templates = BTreeMap::new();
+    for file in template.files.values() {
+        if template.verbatim.contains_key(&file.template) {
+            continue;
+        }
+        let source = template
+            .env
+            .get_template(&file.template)
+            .with_template(&file.template)?
+            .source()
+            .to_owned();
+        templates.insert(file.template.to_owned(), source);
+    }
+
+    let toolchain = template.toolchain.clone();
+    let mut inputs = template.inputs.clone();
+    inputs.insert(
+        reuse::FORMAT_INPUT.to_owned(),
+        options.reuse_format.as_str().to_owned(),
+    );
+    let mut hooks = if options.skip_default_hooks {
+        Vec::new()
+    } else {
+        template.hooks.clone()
+    };
+    hooks.extend(options.hooks.iter().cloned());
+
+    let copyright = template.authorship.copyright();
+    let mut plan = template.render()?;
+    plan.root = project_path.to_path_buf();
+    plan.hooks = hooks;
+    headers::add_headers(
+        &mut plan.files,
+        project_path,
+        &copyright,
+        licensing.expression(),
+        &options.annotations,
+        &options.licenses,
+    );
+
+    // Records the generation in the project root
+    let manifest = Manifest::new(
+        &toolchain,
+        inputs,
+        components,
+        options.vars.clone(),
+        templates,
+        &plan,
+        project_path,
+    );
+    let manifest_path = project_path.join(MANIFEST_FILE);
+    let mut content = manifest.to_toml()?;
+    if let Some(header) = headers::comment_header(
+        &manifest_path,
+        &copyright,
+        licensing.expression(),
+        &options.licenses,
+    ) {
+        content = headers::prepend(&header, &content);
+    }
+    plan.files.push(PlannedFile {
+        path: manifest_path,
+        template: "manifest".to_owned(),
+        content,
+        attributes: FileAttributes::default(),
+        component: None,
+    });
+    plan.files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authorship() -> Authorship {
+        Authorship {
+            author: "Jane Doe".to_owned(),
+            email: String::new(),
+            holder: "Jane Doe".to_owned(),
+            year: "2026".to_owned(),
+        }
+    }
+
+    #[test]
+    fn copyright_line_is_filled() {
+        let text = "MIT License\n\nCopyright (c) <year> <copyright holders>\n\nThe <year> text\n";
+
+        assert_eq!(
+            authorship().fill("MIT", text),
+            "MIT License\n\nCopyright (c) 2026 Jane Doe\n\nThe <year> text\n"
+        );
+    }
 
-    template.render()
+    #[test]
+    fn other_licenses_are_verbatim() {
+        let text = "How to apply\n\n     Copyright (C) <year>  <name of author>\n";
+
+        assert_eq!(authorship().fill("GPL-3.0-only", text), text);
+        assert_eq!(authorship().fill("LicenseRef-ACME", text), text);
+    }
+
+    #[test]
+    fn custom_license_text_is_verbatim() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("acme.txt");
+        std::fs::write(&text, "ACME {{ secret }} {% raw %}\n").unwrap();
+        let mut request = GenerationRequest::new(dir.path().join("project"));
+        request.license = "LicenseRef-ACME".to_owned();
+        request.options.licenses = vec![CustomLicense {
+            id: "LicenseRef-ACME".to_owned(),
+            name: "ACME License".to_owned(),
+            text,
+            header: None,
+        }];
+
+        let plan = Registry::builtin()
+            .find("poetry")
+            .unwrap()
+            .plan(&request)
+            .unwrap();
+        let content = |path: &str| {
+            let path = request.project_path.join(path);
+            plan.files
+                .iter()
+                .find(|file| file.path == path)
+                .map(|file| file.content.as_str())
+        };
+
+        assert_eq!(
+            content("LICENSES/LicenseRef-ACME.txt"),
+            Some("ACME {{ secret }} {% raw %}\n")
+        );
+        assert_eq!(content("LICENSE.md"), Some("ACME {{ secret }} {% raw %}\n"));
+    }
+
+    #[test]
+    fn skipped_files_are_not_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let request = GenerationRequest::new(dir.path().join("project"));
+        let plan = Registry::builtin()
+            .find("poetry")
+            .unwrap()
+            .plan(&request)
+            .unwrap();
+        std::fs::create_dir(&request.project_path).unwrap();
+        std::fs::write(request.project_path.join("README.md"), "Existing\n").unwrap();
+
+        plan.write(ConflictPolicy::Skip).unwrap();
+
+        let manifest = Manifest::read(&request.project_path).unwrap();
+        assert!(!manifest.files.contains_key("README.md"));
+        assert!(manifest.files.contains_key("LICENSE.md"));
+        assert_eq!(manifest.files.len(), plan.files.len() - 2);
+        assert!(
+            std::fs::read_to_string(request.project_path.join(MANIFEST_FILE))
+                .unwrap()
+                .starts_with("# SPDX-FileCopyrightText")
+        );
+    }
+
+    #[test]
+    fn authorship_is_escaped() {
+        let content = |toolchain: &str, author: &str, path: &str| {
+            let mut request = GenerationRequest::new("project");
+            request.author = author.to_owned();
+            request
+                .inputs
+                .insert("group".to_owned(), "org.example".to_owned());
+            let plan = Registry::builtin()
+                .find(toolchain)
+                .unwrap()
+                .plan(&request)
+                .unwrap();
+            let path = request.project_path.join(path);
+            plan.files
+                .into_iter()
+                .find(|file| file.path == path)
+                .unwrap()
+                .content
+        };
+
+        let pom = content("maven", "AT&T \"Labs\"", "pom.xml");
+        assert!(pom.contains("<name>AT&amp;T &quot;Labs&quot;</name>"));
+
+        let pyproject: toml::Value =
+            toml::from_str(&content("poetry", "Jane \"JD\" Doe", "pyproject.toml")).unwrap();
+        assert_eq!(
+            pyproject["tool"]["poetry"]["authors"][0].as_str(),
+            Some("Jane \"JD\" Doe")
+        );
+    }
+
+    #[test]
+    fn reuse_toml_values_are_quoted() {
+        let mut request = GenerationRequest::new("project");
+        request.author = "Jane \"JD\" Doe".to_owned();
+        request.options.annotations = vec![Annotation {
+            paths: vec!["docs/\"quoted\".md".to_owned()],
+            license: "MIT".to_owned(),
+            copyright: "ACME \"Inc.\"".to_owned(),
+        }];
+        let plan = Registry::builtin()
+            .find("poetry")
+            .unwrap()
+            .plan(&request)
+            .unwrap();
+        let path = request.project_path.join("REUSE.toml");
+        let content = &plan
+            .files
+            .iter()
+            .find(|file| file.path == path)
+            .unwrap()
+            .content;
+
+        let reuse: toml::Value = toml::from_str(content).unwrap();
+        assert_eq!(
+            reuse["SPDX-PackageSupplier"].as_str(),
+            Some("Jane \"JD\" Doe")
+        );
+        let annotation = &reuse["annotations"][1];
+        assert_eq!(annotation["path"][0].as_str(), Some("docs/\"quoted\".md"));
+        assert_eq!(
+            annotation["SPDX-FileCopyrightText"].as_str(),
+            Some("ACME \"Inc.\"")
+        );
+    }
+
+    #[test]
+    fn pom_lists_the_license_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("acme.txt");
+        std::fs::write(&text, "ACME license\n").unwrap();
+        let mut request = GenerationRequest::new(dir.path().join("project"));
+        request.license = "LicenseRef-ACME OR MIT".to_owned();
+        request
+            .inputs
+            .insert("group".to_owned(), "org.example".to_owned());
+        request.options.licenses = vec![CustomLicense {
+            id: "LicenseRef-ACME".to_owned(),
+            name: "ACME & Co. License".to_owned(),
+            text,
+            header: None,
+        }];
+
+        let plan = Registry::builtin()
+            .find("maven")
+            .unwrap()
+            .plan(&request)
+            .unwrap();
+        let path = request.project_path.join("pom.xml");
+        let pom = &plan
+            .files
+            .iter()
+            .find(|file| file.path == path)
+            .unwrap()
+            .content;
+
+        assert!(pom.contains("<name>ACME &amp; Co. License</name>"));
+        assert!(pom.contains("<name>MIT License</name>"));
+        assert!(pom.contains("<url>https://spdx.org/licenses/MIT.html</url>"));
+        assert!(!pom.contains("LicenseRef-ACME.html"));
+    }
+
+    #[test]
+    fn readme_links_every_license_text() {
+        let mut request = GenerationRequest::new("project");
+        request.license = "MIT OR Apache-2.0 WITH LLVM-exception".to_owned();
+        let plan = Registry::builtin()
+            .find("poetry")
+            .unwrap()
+            .plan(&request)
+            .unwrap();
+        let path = request.project_path.join("README.md");
+        let readme = &plan
+            .files
+            .iter()
+            .find(|file| file.path == path)
+            .unwrap()
+            .content;
+
+        for id in ["MIT", "Apache-2.0", "LLVM-exception"] {
+            assert!(readme.contains(&format!("[{id}](LICENSES/{id}.txt)")));
+        }
+        assert!(readme.contains("[license]: #license"));
+    }
+
+    #[test]
+    fn components_are_filtered() {
+        let mut request = GenerationRequest::new("project");
+        request.options.without = vec!["docker".to_owned(), "fuzz".to_owned()];
+
+        // The docker image description is only needed by the docker component
+        let plan = Registry::builtin()
+            .find("cargo")
+            .unwrap()
+            .plan(&request)
+            .unwrap();
+        let generated = |path: &str| {
+            let path = request.project_path.join(path);
+            plan.files.iter().any(|file| file.path == path)
+        };
+
+        assert!(generated("README.md"));
+        assert!(generated(".github/workflows/project.yml"));
+        assert!(!generated("docker/Dockerfile-amd64"));
+        assert!(!generated("fuzz/Cargo.toml"));
+        assert!(!plan.dirs.contains(&request.project_path.join("fuzz")));
+
+        request.options.without.clear();
+        request.options.only = vec!["ci".to_owned()];
+        let plan = Registry::builtin()
+            .find("cargo")
+            .unwrap()
+            .plan(&request)
+            .unwrap();
+        assert!(plan
+            .files
+            .iter()
+            .filter_map(|file| file.component.as_deref())
+            .all(|component| component == "ci"));
+    }
+
+    #[test]
+    fn docker_image_description_is_required_with_docker() {
+        let request = GenerationRequest::new("project");
+
+        assert!(matches!(
+            Registry::builtin().find("cargo").unwrap().plan(&request),
+            Err(Error::MissingInput { input, .. }) if input == "docker_image_description"
+        ));
+    }
+
+    #[test]
+    fn unknown_components_are_rejected() {
+        let mut request = GenerationRequest::new("project");
+        request.options.only = vec!["fuz".to_owned()];
+
+        assert!(matches!(
+            Registry::builtin().find("poetry").unwrap().plan(&request),
+            Err(Error::UnknownComponent { name, .. }) if name == "fuz"
+        ));
+
+        // The components of the other toolchains are accepted
+        request.options.only = vec!["fuzz".to_owned()];
+        assert!(Registry::builtin()
+            .find("poetry")
+            .unwrap()
+            .plan(&request)
+            .is_ok());
+    }
+
+    #[test]
+    fn plan_is_deterministic_and_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut request = GenerationRequest::new(dir.path().join("project"));
+        request.inputs.insert(
+            "docker_image_description".to_owned(),
+            "Description".to_owned(),
+        );
+        let registry = Registry::builtin();
+        let cargo = registry.find("cargo").unwrap();
+
+        let first = cargo.plan(&request).unwrap();
+        let second = cargo.plan(&request).unwrap();
+
+        assert_eq!(first, second);
+        let mut dirs = first.dirs.clone();
+        dirs.sort();
+        assert_eq!(first.dirs, dirs);
+        let mut files = first.files.clone();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(first.files, files);
+        assert!(!request.project_path.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn abort_and_skip_keep_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let request = GenerationRequest::new(dir.path().join("project"));
+        let plan = Registry::builtin()
+            .find("poetry")
+            .unwrap()
+            .plan(&request)
+            .unwrap();
+        let existing = [
+            request.project_path.join("README.md"),
+            request.project_path.join("pyproject.toml"),
+        ];
+        std::fs::create_dir(&request.project_path).unwrap();
+        for path in &existing {
+            std::fs::write(path, "Existing\n").unwrap();
+        }
+
+        match plan.write(ConflictPolicy::Abort) {
+            Err(Error::Conflicts { paths }) => assert_eq!(paths, existing),
+            result => panic!("{result:?} is not a conflict"),
+        }
+        assert_eq!(
+            std::fs::read_dir(&request.project_path).unwrap().count(),
+            existing.len()
+        );
+
+        plan.write(ConflictPolicy::Skip).unwrap();
+        for path in &existing {
+            assert_eq!(std::fs::read_to_string(path).unwrap(), "Existing\n");
+        }
+        assert!(request.project_path.join("LICENSE.md").exists());
+    }
+
+    #[test]
+    fn user_templates_replace_the_builtin_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let [high, low] = ["high", "low"].map(|name| dir.path().join(name));
+        for (templates_dir, content) in [(&high, "High {{ name }}\n"), (&low, "Low\n")] {
+            std::fs::create_dir_all(templates_dir.join("poetry")).unwrap();
+            std::fs::write(templates_dir.join("poetry/README.md"), content).unwrap();
+        }
+        let mut request = GenerationRequest::new("project");
+        request.options.templates_dirs = vec![high.clone(), low];
+        let registry = Registry::builtin();
+        let poetry = registry.find("poetry").unwrap();
+
+        let plan = poetry.plan(&request).unwrap();
+        let path = request.project_path.join("README.md");
+        let readme = &plan
+            .files
+            .iter()
+            .find(|file| file.path == path)
+            .unwrap()
+            .content;
+        assert!(readme.ends_with("\n\nHigh project"));
+
+        let unknown = high.join("poetry/unknown.md");
+        std::fs::write(&unknown, "").unwrap();
+        assert!(matches!(
+            poetry.plan(&request),
+            Err(Error::UnknownTemplate { toolchain, path }) if toolchain == "poetry" && path == unknown
+        ));
+    }
 }
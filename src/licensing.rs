@@ -0,0 +1,292 @@
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use license::{Exception, License};
+use spdx::error::Reason;
+use spdx::{Expression, LicenseItem};
+
+use crate::error::WithPath;
+use crate::{Error, Result};
+
+/// A license outside of the SPDX list, such as a proprietary one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomLicense {
+    /// Identifier used in the expressions, in the `LicenseRef-<name>` form.
+    pub id: String,
+    /// Full name of the license.
+    pub name: String,
+    /// Path to the text of the license.
+    pub text: PathBuf,
+    /// Notice to be put at the top of the files, if any.
+    pub header: Option<String>,
+}
+
+/// The text of a license or an exception.
+#[derive(Debug, Clone)]
+struct Text {
+    id: String,
+    name: String,
+    // Page of the SPDX list, custom licenses have none
+    url: Option<String>,
+    text: String,
+}
+
+impl Text {
+    fn license(license: &dyn License) -> Self {
+        Self {
+            id: license.id().to_owned(),
+            name: license.name().to_owned(),
+            url: Some(spdx_url(license.id())),
+            text: license.text().to_owned(),
+        }
+    }
+
+    fn exception(exception: &dyn Exception) -> Self {
+        Self {
+            id: exception.id().to_owned(),
+            name: exception.name().to_owned(),
+            url: Some(spdx_url(exception.id())),
+            text: exception.text().to_owned(),
+        }
+    }
+
+    fn custom(license: &CustomLicense) -> Result<Self> {
+        Ok(Self {
+            id: license.id.to_owned(),
+            name: license.name.to_owned(),
+            url: None,
+            text: read_to_string(&license.text).with_path(&license.text)?,
+        })
+    }
+}
+
+fn spdx_url(id: &str) -> String {
+    format!("https://spdx.org/licenses/{id}.html")
+}
+
+/// The licenses of a project, as an SPDX expression such as
+/// `MIT OR Apache-2.0` or `Apache-2.0 WITH LLVM-exception`.
+#[derive(Debug, Clone)]
+pub struct Licensing {
+    expression: String,
+    licenses: Vec<Text>,
+    exceptions: Vec<Text>,
+}
+
+impl Licensing {
+    /// Parses an SPDX expression whose licenses and exceptions are all
+    /// known, either from the SPDX list or among the `custom` ones.
+    pub fn parse(expression: &str, custom: &[CustomLicense]) -> Result<Self> {
+        let expression = expression.trim();
+        let parsed = Expression::parse(expression).map_err(|e| match e.reason {
+            Reason::UnknownLicense | Reason::UnknownException => Error::UnknownLicense {
+                license: e.original[e.span].to_owned(),
+            },
+            reason => {
+                let term = &e.original[e.span];
+                Error::LicenseExpression {
+                    expression: expression.to_owned(),
+                    reason: if term.is_empty() {
+                        reason.to_string()
+                    } else {
+                        format!("{reason} `{term}`")
+                    },
+                }
+            }
+        })?;
+
+        let mut licenses: Vec<Text> = Vec::new();
+        let mut exceptions: Vec<Text> = Vec::new();
+        for requirement in parsed.requirements() {
+            let license = match &requirement.req.license {
+                LicenseItem::Spdx { id, .. } => {
+                    // The GNU licenses are parsed without their `-only` or
+                    // `-or-later` suffix, which their identifier keeps
+                    let span = requirement.span.start as usize..requirement.span.end as usize;
+                    let term = expression.get(span).unwrap_or(id.name);
+                    term.parse::<&dyn License>()
+                        .or_else(|_| id.name.parse::<&dyn License>())
+                        .map(Text::license)
+                        .map_err(|_| Error::UnknownLicense {
+                            license: id.name.to_owned(),
+                        })?
+                }
+                LicenseItem::Other { doc_ref, lic_ref } => {
+                    let id = match doc_ref {
+                        Some(doc_ref) => format!("DocumentRef-{doc_ref}:LicenseRef-{lic_ref}"),
+                        None => format!("LicenseRef-{lic_ref}"),
+                    };
+                    let license = custom
+                        .iter()
+                        .find(|license| license.id == id)
+                        .ok_or(Error::UnknownLicense { license: id })?;
+                    Text::custom(license)?
+                }
+            };
+            if !licenses.iter().any(|known| known.id == license.id) {
+                licenses.push(license);
+            }
+
+            if let Some(exception) = requirement.req.exception {
+                let exception = exception
+                    .name
+                    .parse::<&dyn Exception>()
+                    .map(Text::exception)
+                    .map_err(|_| Error::UnknownLicense {
+                        license: exception.name.to_owned(),
+                    })?;
+                if !exceptions.iter().any(|known| known.id == exception.id) {
+                    exceptions.push(exception);
+                }
+            }
+        }
+
+        Ok(Self {
+            expression: expression.to_owned(),
+            licenses,
+            exceptions,
+        })
+    }
+
+    /// Returns the expression as passed.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns the identifiers of the licenses.
+    pub fn license_ids(&self) -> Vec<&str> {
+        self.licenses
+            .iter()
+            .map(|license| license.id.as_str())
+            .collect()
+    }
+
+    /// Returns the identifier, the name and the URL of each license, the
+    /// custom ones have no URL.
+    pub(crate) fn licenses(&self) -> impl Iterator<Item = (&str, &str, Option<&str>)> + '_ {
+        self.licenses.iter().map(|license| {
+            (
+                license.id.as_str(),
+                license.name.as_str(),
+                license.url.as_deref(),
+            )
+        })
+    }
+
+    /// Returns the identifiers of the exceptions.
+    pub fn exception_ids(&self) -> Vec<&str> {
+        self.exceptions
+            .iter()
+            .map(|exception| exception.id.as_str())
+            .collect()
+    }
+
+    /// Returns the identifiers of the licenses and the exceptions along with
+    /// their texts.
+    pub(crate) fn texts(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.licenses
+            .iter()
+            .chain(&self.exceptions)
+            .map(|text| (text.id.as_str(), text.text.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use super::*;
+
+    #[test]
+    fn compound_expressions() {
+        let licensing = Licensing::parse(" MIT OR (Apache-2.0 AND MIT) ", &[]).unwrap();
+
+        assert_eq!(licensing.expression(), "MIT OR (Apache-2.0 AND MIT)");
+        assert_eq!(licensing.license_ids(), ["MIT", "Apache-2.0"]);
+        assert!(licensing.exception_ids().is_empty());
+        assert_eq!(licensing.texts().count(), 2);
+    }
+
+    #[test]
+    fn exceptions() {
+        let licensing = Licensing::parse("Apache-2.0 WITH LLVM-exception", &[]).unwrap();
+
+        assert_eq!(licensing.license_ids(), ["Apache-2.0"]);
+        assert_eq!(licensing.exception_ids(), ["LLVM-exception"]);
+        assert_eq!(
+            licensing.texts().map(|(id, _)| id).collect::<Vec<_>>(),
+            ["Apache-2.0", "LLVM-exception"]
+        );
+    }
+
+    #[test]
+    fn gnu_licenses_keep_their_suffix() {
+        let licensing = Licensing::parse(
+            "GPL-3.0-or-later WITH GCC-exception-3.1 OR LGPL-2.1-only",
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(
+            licensing.license_ids(),
+            ["GPL-3.0-or-later", "LGPL-2.1-only"]
+        );
+        assert_eq!(licensing.exception_ids(), ["GCC-exception-3.1"]);
+    }
+
+    #[test]
+    fn unknown_ids() {
+        // The terms which are neither licenses nor operators are reported
+        for (expression, term) in [
+            ("MIT OR Foo-1.0", "Foo-1.0"),
+            ("MIT WITH Foo-exception", "Foo-exception"),
+        ] {
+            match Licensing::parse(expression, &[]) {
+                Err(Error::LicenseExpression { reason, .. }) => {
+                    assert_eq!(reason, format!("unknown term `{term}`"))
+                }
+                result => panic!("{result:?} for {expression}"),
+            }
+        }
+        assert!(matches!(
+            Licensing::parse("MIT OR", &[]),
+            Err(Error::LicenseExpression { .. })
+        ));
+    }
+
+    #[test]
+    fn custom_licenses() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("acme.txt");
+        write(&text, "ACME license\n").unwrap();
+        let custom = [CustomLicense {
+            id: "LicenseRef-ACME".to_owned(),
+            name: "ACME License".to_owned(),
+            text,
+            header: None,
+        }];
+
+        let licensing = Licensing::parse("LicenseRef-ACME OR MIT", &custom).unwrap();
+        assert_eq!(licensing.license_ids(), ["LicenseRef-ACME", "MIT"]);
+        assert_eq!(
+            licensing.licenses().collect::<Vec<_>>(),
+            [
+                ("LicenseRef-ACME", "ACME License", None),
+                (
+                    "MIT",
+                    "MIT License",
+                    Some("https://spdx.org/licenses/MIT.html")
+                )
+            ]
+        );
+        assert_eq!(
+            licensing.texts().next(),
+            Some(("LicenseRef-ACME", "ACME license\n"))
+        );
+
+        assert!(matches!(
+            Licensing::parse("LicenseRef-Other", &custom),
+            Err(Error::UnknownLicense { license }) if license == "LicenseRef-Other"
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::WithPath;
+use crate::{Error, Plan, Result};
+
+/// Name of the manifest file stored in the root of a generated project.
+pub const MANIFEST_FILE: &str = ".sifis-generate.toml";
+
+/// Record of a generation: what produced the files and what they contained.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Version of sifis-generate which produced the files.
+    pub version: String,
+    /// Toolchain used to generate the files.
+    pub toolchain: String,
+    /// Resolved inputs of the generation.
+    pub inputs: BTreeMap<String, String>,
+    /// Components generated, all of them when empty.
+    #[serde(default)]
+    pub components: Vec<String>,
+    /// Extra variables made available to the templates.
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    /// Content hash of each generated file, keyed by its path relative to
+    /// the project root.
+    pub files: BTreeMap<String, String>,
+    /// Sources of the templates used to generate the files.
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub(crate) fn new(
+        toolchain: &str,
+        inputs: BTreeMap<String, String>,
+        components: Vec<String>,
+        vars: BTreeMap<String, String>,
+        templates: BTreeMap<String, String>,
+        plan: &Plan,
+        project_path: &Path,
+    ) -> Self {
+        let files = plan
+            .files
+            .iter()
+            .map(|file| {
+                (
+                    relative_path(&file.path, project_path),
+                    hash(file.content.as_bytes()),
+                )
+            })
+            .collect();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            toolchain: toolchain.to_owned(),
+            inputs,
+            components,
+            vars,
+            files,
+            templates,
+        }
+    }
+
+    /// Reads the manifest stored in the root of a project.
+    pub fn read(project_path: &Path) -> Result<Self> {
+        let path = project_path.join(MANIFEST_FILE);
+        let content = read_to_string(&path).with_path(&path)?;
+        Self::parse(&content, path)
+    }
+
+    /// Parses the content of a manifest stored in `path`.
+    pub(crate) fn parse(content: &str, path: PathBuf) -> Result<Self> {
+        toml::from_str(content).map_err(|e| Error::Manifest {
+            path,
+            source: e.into(),
+        })
+    }
+
+    /// Removes `paths`, relative to the project root, from the files of the
+    /// manifest serialized in `content`, keeping its leading comments.
+    pub(crate) fn without_files(content: &str, paths: &[String]) -> Result<String> {
+        let mut manifest = Self::parse(content, MANIFEST_FILE.into())?;
+        for path in paths {
+            manifest.files.remove(path);
+        }
+
+        let header = content
+            .split_inclusive('\n')
+            .take_while(|line| line.starts_with('#') || line.trim().is_empty())
+            .collect::<String>();
+
+        Ok(header + &manifest.to_toml()?)
+    }
+
+    /// Serializes the manifest in the TOML format.
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(self).map_err(|e| Error::Manifest {
+            path: MANIFEST_FILE.into(),
+            source: e.into(),
+        })
+    }
+}
+
+/// Computes the content hash recorded in a [`Manifest`].
+pub fn hash(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("sha256:{hex}")
+}
+
+/// Returns `path` relative to `root` with `/` as separator.
+pub(crate) fn relative_path(path: &Path, root: &Path) -> String {
+    let path = path.strip_prefix(root).unwrap_or(path);
+    path.components()
+        .filter(|component| component != &Component::CurDir)
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
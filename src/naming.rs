@@ -0,0 +1,547 @@
+use crate::{Error, Result};
+
+// Keywords and reserved words of Rust, which cannot name a crate
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+// Crates shipped with the Rust toolchain, refused by cargo
+const RUST_BUILTIN_CRATES: &[&str] = &["alloc", "core", "proc_macro", "std", "test"];
+
+// Keywords of Python, which cannot name a module
+const PYTHON_KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+    "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+// Keywords and literals of Java, which cannot be part of a package
+const JAVA_KEYWORDS: &[&str] = &[
+    "_",
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+];
+
+// Keywords of C and of the meson language, which cannot be identifiers
+const C_KEYWORDS: &[&str] = &[
+    "and",
+    "auto",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "elif",
+    "else",
+    "endforeach",
+    "endif",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "foreach",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "not",
+    "or",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "true",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+];
+
+// Names npm refuses to publish
+const NPM_RESERVED: &[&str] = &["favicon.ico", "node_modules"];
+
+/// A rule returning why a name is not valid, if it is not.
+type Rule = fn(&str) -> Option<String>;
+
+/// Checks `name` against `rule`, suggesting the output of `normalize` when it
+/// is valid.
+fn check(toolchain: &str, name: &str, rule: Rule, normalize: fn(&str) -> String) -> Result<()> {
+    let Some(reason) = rule(name) else {
+        return Ok(());
+    };
+    let suggestion = normalize(name);
+    let suggestion = (!suggestion.is_empty() && suggestion != name && rule(&suggestion).is_none())
+        .then_some(suggestion);
+
+    Err(Error::InvalidName {
+        toolchain: toolchain.to_owned(),
+        name: name.to_owned(),
+        reason,
+        suggestion,
+    })
+}
+
+/// Lowercases `name`, replaces each run of characters not `allowed` with
+/// `separator` and drops the characters which cannot start or end a name.
+fn sanitize(name: &str, allowed: fn(char) -> bool, separator: char) -> String {
+    let mut sanitized = String::new();
+    for c in name.to_lowercase().chars() {
+        if allowed(c) {
+            sanitized.push(c);
+        } else if !sanitized.is_empty() && !sanitized.ends_with(separator) {
+            sanitized.push(separator);
+        }
+    }
+
+    sanitized
+        .trim_start_matches(|c: char| !c.is_ascii_alphabetic())
+        .trim_end_matches(|c: char| !c.is_ascii_alphanumeric())
+        .to_owned()
+}
+
+fn is_ascii_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Checks a crate name, as accepted by `cargo new`.
+pub(crate) fn check_crate(toolchain: &str, name: &str) -> Result<()> {
+    check(toolchain, name, crate_rule, |name| {
+        sanitize(name, |c| is_ascii_word(c) || c == '-', '-')
+    })
+}
+
+fn crate_rule(name: &str) -> Option<String> {
+    if name.len() > 64 {
+        return Some("it must be at most 64 characters long".to_owned());
+    }
+    if !name.chars().all(|c| is_ascii_word(c) || c == '-') {
+        return Some("it may only contain ASCII letters, digits, `-` and `_`".to_owned());
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Some("it must start with a letter".to_owned());
+    }
+    let crate_name = crate_name(name);
+    if RUST_KEYWORDS.contains(&crate_name.as_str()) {
+        return Some(format!("`{crate_name}` is a Rust keyword"));
+    }
+    if RUST_BUILTIN_CRATES.contains(&crate_name.as_str()) {
+        return Some(format!(
+            "it conflicts with the built-in `{crate_name}` crate"
+        ));
+    }
+
+    None
+}
+
+/// Name of a crate in the Rust code, with underscores in place of hyphens.
+pub(crate) fn crate_name(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+/// Checks a Python distribution name, following PEP 508, whose module name
+/// must be a Python identifier.
+pub(crate) fn check_python(toolchain: &str, name: &str) -> Result<()> {
+    check(toolchain, name, python_rule, |name| {
+        sanitize(name, |c| is_ascii_word(c) || c == '-' || c == '.', '-')
+    })
+}
+
+fn python_rule(name: &str) -> Option<String> {
+    if !name
+        .chars()
+        .all(|c| is_ascii_word(c) || c == '-' || c == '.')
+    {
+        return Some("it may only contain ASCII letters, digits, `.`, `-` and `_`".to_owned());
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        || !name.ends_with(|c: char| c.is_ascii_alphanumeric())
+    {
+        return Some("it must start and end with a letter or a digit".to_owned());
+    }
+    let module_name = python_module(name);
+    if module_name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Some(format!(
+            "the `{module_name}` module must not start with a digit"
+        ));
+    }
+    if PYTHON_KEYWORDS.contains(&module_name.as_str()) {
+        return Some(format!("`{module_name}` is a Python keyword"));
+    }
+
+    None
+}
+
+/// Normalised distribution name, as defined by PEP 503.
+pub(crate) fn python_package(name: &str) -> String {
+    let mut package = String::new();
+    for c in name.to_lowercase().chars() {
+        if !matches!(c, '-' | '_' | '.') {
+            package.push(c);
+        } else if !package.ends_with('-') {
+            package.push('-');
+        }
+    }
+
+    package
+}
+
+/// Name of the module imported by the Python code.
+pub(crate) fn python_module(name: &str) -> String {
+    python_package(name).replace('-', "_")
+}
+
+/// Checks a maven artifactId, whose Java package must be valid.
+pub(crate) fn check_artifact(toolchain: &str, name: &str) -> Result<()> {
+    check(toolchain, name, artifact_rule, |name| {
+        sanitize(name, |c| is_ascii_word(c) || c == '-' || c == '.', '-')
+    })
+}
+
+fn artifact_rule(name: &str) -> Option<String> {
+    if !name
+        .chars()
+        .all(|c| is_ascii_word(c) || c == '-' || c == '.')
+    {
+        return Some("it may only contain ASCII letters, digits, `.`, `-` and `_`".to_owned());
+    }
+    java_segment_rule(&java_segment(name))
+}
+
+/// Checks a maven groupId, which is also the prefix of the Java package.
+pub(crate) fn check_group(group: &str) -> Result<()> {
+    let Some(reason) = group
+        .split('.')
+        .find_map(java_segment_rule)
+        .map(|reason| format!("each part of the Java package must be valid, {reason}"))
+    else {
+        return Ok(());
+    };
+
+    let suggestion = group
+        .split('.')
+        .map(|segment| sanitize(segment, is_ascii_word, '_'))
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join(".");
+    let suggestion = (!suggestion.is_empty()
+        && suggestion
+            .split('.')
+            .all(|s| java_segment_rule(s).is_none()))
+    .then_some(suggestion);
+
+    Err(Error::InvalidInput {
+        input: "group".to_owned(),
+        value: group.to_owned(),
+        reason,
+        suggestion,
+    })
+}
+
+fn java_segment_rule(segment: &str) -> Option<String> {
+    if segment.is_empty() {
+        return Some("empty parts are not allowed".to_owned());
+    }
+    if !segment.chars().all(|c| is_ascii_word(c) || c == '$') {
+        return Some(format!(
+            "`{segment}` may only contain ASCII letters, digits, `$` and `_`"
+        ));
+    }
+    if segment.starts_with(|c: char| c.is_ascii_digit()) {
+        return Some(format!("`{segment}` must not start with a digit"));
+    }
+    if JAVA_KEYWORDS.contains(&segment) {
+        return Some(format!("`{segment}` is a Java keyword"));
+    }
+
+    None
+}
+
+/// Part of the Java package derived from an artifactId.
+pub(crate) fn java_segment(name: &str) -> String {
+    name.to_lowercase().replace(['-', '.'], "_")
+}
+
+/// Checks an npm package name, scoped or not.
+pub(crate) fn check_npm(toolchain: &str, name: &str) -> Result<()> {
+    check(toolchain, name, npm_rule, |name| {
+        let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~');
+        match name.strip_prefix('@').and_then(|name| name.split_once('/')) {
+            Some((scope, name)) => {
+                let scope = sanitize(scope, allowed, '-');
+                let name = sanitize(name, allowed, '-');
+                if scope.is_empty() || name.is_empty() {
+                    String::new()
+                } else {
+                    format!("@{scope}/{name}")
+                }
+            }
+            None => sanitize(name, allowed, '-'),
+        }
+    })
+}
+
+fn npm_rule(name: &str) -> Option<String> {
+    if name.len() > 214 {
+        return Some("it must be at most 214 characters long".to_owned());
+    }
+    if name.chars().any(|c| c.is_uppercase()) {
+        return Some("it must not contain uppercase letters".to_owned());
+    }
+    if NPM_RESERVED.contains(&name) {
+        return Some(format!("`{name}` is reserved by npm"));
+    }
+    let parts = match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            Some((scope, name)) if !scope.is_empty() && !name.is_empty() => vec![scope, name],
+            _ => return Some("a scoped name must be in the `@scope/name` form".to_owned()),
+        },
+        None => vec![name],
+    };
+    for part in parts {
+        if part.starts_with(['.', '_']) {
+            return Some("it must not start with `.` or `_`".to_owned());
+        }
+        if !part
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~'))
+        {
+            return Some(
+                "it may only contain lowercase ASCII letters, digits, `-`, `.`, `_` and `~`"
+                    .to_owned(),
+            );
+        }
+    }
+
+    None
+}
+
+/// Stem of the files named after an npm package, without the scope marker
+/// and the slash.
+pub(crate) fn npm_file_stem(name: &str) -> String {
+    name.trim_start_matches('@').replace('/', "-")
+}
+
+/// Checks a name whose underscored form is a C identifier.
+pub(crate) fn check_c(toolchain: &str, name: &str) -> Result<()> {
+    check(toolchain, name, c_rule, |name| {
+        sanitize(name, |c| is_ascii_word(c) || c == '-', '-')
+    })
+}
+
+fn c_rule(name: &str) -> Option<String> {
+    if !name.chars().all(|c| is_ascii_word(c) || c == '-') {
+        return Some("it may only contain ASCII letters, digits, `-` and `_`".to_owned());
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return Some("it must start with a letter or `_`".to_owned());
+    }
+    let identifier = c_identifier(name);
+    if C_KEYWORDS.contains(&identifier.as_str()) {
+        return Some(format!("`{identifier}` is a reserved word"));
+    }
+
+    None
+}
+
+/// C identifier derived from a name, with underscores in place of hyphens.
+pub(crate) fn c_identifier(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The suggestion of a rejected name or group, `None` when there is none
+    fn suggestion(result: Result<()>) -> Option<String> {
+        match result {
+            Err(Error::InvalidName { suggestion, .. } | Error::InvalidInput { suggestion, .. }) => {
+                suggestion
+            }
+            result => panic!("{result:?} is not an invalid name"),
+        }
+    }
+
+    #[test]
+    fn crate_names() {
+        for name in ["foo", "foo-bar", "foo_bar2", "Foo"] {
+            assert!(check_crate("cargo", name).is_ok(), "{name}");
+        }
+
+        assert_eq!(
+            suggestion(check_crate("cargo", "My Crate")).unwrap(),
+            "my-crate"
+        );
+        assert_eq!(suggestion(check_crate("cargo", "2fast")).unwrap(), "fast");
+        assert_eq!(
+            suggestion(check_crate("cargo", "foo.bar!")).unwrap(),
+            "foo-bar"
+        );
+        assert_eq!(suggestion(check_crate("cargo", &"a".repeat(65))), None);
+        assert_eq!(suggestion(check_crate("cargo", "self")), None);
+        assert_eq!(suggestion(check_crate("cargo", "proc-macro")), None);
+        assert_eq!(suggestion(check_crate("cargo", "!!!")), None);
+
+        assert_eq!(crate_name("foo-bar"), "foo_bar");
+    }
+
+    #[test]
+    fn python_names() {
+        for name in ["foo", "foo.bar", "foo-bar", "Foo_Bar", "x2"] {
+            assert!(check_python("poetry", name).is_ok(), "{name}");
+        }
+
+        assert_eq!(
+            suggestion(check_python("poetry", "my project")).unwrap(),
+            "my-project"
+        );
+        assert_eq!(suggestion(check_python("poetry", "-foo-")).unwrap(), "foo");
+        assert_eq!(suggestion(check_python("poetry", "2fast")).unwrap(), "fast");
+        assert_eq!(suggestion(check_python("poetry", "import")), None);
+        assert_eq!(suggestion(check_python("poetry", "Lambda")), None);
+
+        assert_eq!(python_package("Foo__Bar.baz"), "foo-bar-baz");
+        assert_eq!(python_module("Foo-Bar.baz"), "foo_bar_baz");
+    }
+
+    #[test]
+    fn maven_names() {
+        for name in ["app", "my-app", "my.app", "App2"] {
+            assert!(check_artifact("maven", name).is_ok(), "{name}");
+        }
+
+        assert_eq!(
+            suggestion(check_artifact("maven", "my app")).unwrap(),
+            "my-app"
+        );
+        assert_eq!(suggestion(check_artifact("maven", "1app")).unwrap(), "app");
+        assert_eq!(suggestion(check_artifact("maven", "class")), None);
+
+        assert_eq!(java_segment("My-App.core"), "my_app_core");
+    }
+
+    #[test]
+    fn maven_groups() {
+        for group in ["org.example", "com.example.app_2", "a$b"] {
+            assert!(check_group(group).is_ok(), "{group}");
+        }
+
+        let reason = |group: &str| match check_group(group) {
+            Err(Error::InvalidInput { reason, .. }) => reason,
+            result => panic!("{result:?} is not an invalid input"),
+        };
+        assert!(reason("org..example").contains("empty parts are not allowed"));
+        assert!(reason("org.2example").contains("must not start with a digit"));
+        assert_eq!(
+            suggestion(check_group("org.example.")).unwrap(),
+            "org.example"
+        );
+        assert_eq!(
+            suggestion(check_group("Org.My-Company")).unwrap(),
+            "org.my_company"
+        );
+        assert_eq!(suggestion(check_group("org.class")), None);
+    }
+
+    #[test]
+    fn npm_names() {
+        for name in ["foo", "foo-bar", "foo.bar~2", "@scope/foo"] {
+            assert!(check_npm("yarn", name).is_ok(), "{name}");
+        }
+
+        assert_eq!(suggestion(check_npm("yarn", "Foo Bar")).unwrap(), "foo-bar");
+        assert_eq!(suggestion(check_npm("yarn", "_foo")).unwrap(), "foo");
+        assert_eq!(
+            suggestion(check_npm("yarn", "@My Scope/Foo")).unwrap(),
+            "@my-scope/foo"
+        );
+        assert_eq!(suggestion(check_npm("yarn", "@scope")).unwrap(), "scope");
+        assert_eq!(suggestion(check_npm("yarn", "@/foo")), None);
+        assert_eq!(suggestion(check_npm("yarn", "node_modules")), None);
+        assert_eq!(suggestion(check_npm("yarn", &"a".repeat(215))), None);
+
+        assert_eq!(npm_file_stem("@scope/foo"), "scope-foo");
+        assert_eq!(npm_file_stem("foo"), "foo");
+    }
+
+    #[test]
+    fn c_names() {
+        for name in ["foo", "foo-bar", "_foo", "Foo2"] {
+            assert!(check_c("meson", name).is_ok(), "{name}");
+        }
+
+        assert_eq!(suggestion(check_c("meson", "my lib")).unwrap(), "my-lib");
+        assert_eq!(suggestion(check_c("meson", "2d")).unwrap(), "d");
+        assert_eq!(suggestion(check_c("meson", "int")), None);
+        assert_eq!(suggestion(check_c("meson", "endif")), None);
+
+        assert_eq!(c_identifier("foo-bar"), "foo_bar");
+    }
+}
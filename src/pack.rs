@@ -0,0 +1,391 @@
+//! Toolchains defined by template packs.
+//!
+//! A template pack is a directory containing a `pack.toml` file, which
+//! declares the directories and the files of the project, along with the
+//! input variables filled in the templates, and the template files.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{read_dir, read_to_string};
+use std::path::{Path, PathBuf};
+
+use minijinja::value::Value;
+use minijinja::Environment;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{WithPath, WithTemplate};
+use crate::{
+    compute_template, define_name, user_templates, Authorship, Error, FileAttributes,
+    GenerationRequest, InputKind, Licensing, Mode, Plan, ProjectFile, Result, SifisTemplate,
+    Toolchain, ToolchainInput,
+};
+
+/// Name of the file describing a template pack.
+pub const PACK_FILE: &str = "pack.toml";
+
+// Context variables defined for every toolchain
+const RESERVED: &[&str] = &[
+    "name",
+    "branch",
+    "license",
+    "license_id",
+    "license_ids",
+    "licenses",
+    "exception_ids",
+    "reuse",
+    "components",
+    "author",
+    "email",
+    "copyright_holder",
+    "year",
+    "copyright",
+];
+
+/// An input variable of a pack.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackInput {
+    /// Type of the input.
+    #[serde(rename = "type", default)]
+    pub kind: InputKind,
+    /// Description of the input, shown in the command line help.
+    #[serde(default)]
+    pub description: String,
+    /// Default value, the input is required when missing.
+    pub default: Option<toml::Value>,
+    /// Admitted values of a `choice` input.
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+impl PackInput {
+    /// Returns the default value of the input as a string, if any.
+    pub fn default_value(&self) -> Option<String> {
+        self.default.as_ref().map(|value| match value {
+            toml::Value::String(s) => s.to_owned(),
+            value => value.to_string(),
+        })
+    }
+
+    /// Returns the input called `name` as a toolchain input.
+    pub fn to_toolchain_input(&self, name: &str) -> ToolchainInput {
+        ToolchainInput {
+            name: name.to_owned(),
+            kind: self.kind,
+            description: self.description.to_owned(),
+            default: self.default_value(),
+            choices: self.choices.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A file of a pack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackFile {
+    /// Path of the generated file relative to the project root, it is a
+    /// template itself, e.g. `.github/workflows/{{ name }}.yml`.
+    pub path: String,
+    /// Path of the template relative to the pack directory.
+    pub template: String,
+    /// Unix permission bits of the generated file, e.g. `0o755`.
+    pub mode: Option<u32>,
+    /// Component the file belongs to, the file is always generated when missing.
+    pub component: Option<String>,
+}
+
+/// A toolchain defined by a template pack.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pack {
+    /// Name of the toolchain, the name of the pack directory by default.
+    #[serde(default)]
+    pub name: String,
+    /// Description of the toolchain, shown in the command line help.
+    #[serde(default)]
+    pub description: String,
+    /// Directories of the project relative to the project root, they are
+    /// templates themselves.
+    #[serde(default)]
+    pub dirs: Vec<String>,
+    /// Files of the project.
+    #[serde(default)]
+    pub files: Vec<PackFile>,
+    /// Input variables filled in the templates.
+    #[serde(default)]
+    pub inputs: BTreeMap<String, PackInput>,
+    /// Commands run in the project root after the generation.
+    #[serde(default)]
+    pub hooks: Vec<String>,
+    /// Directory containing the pack.
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+impl Pack {
+    /// Loads the pack contained in a directory.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(PACK_FILE);
+        let content = read_to_string(&path).with_path(&path)?;
+        let invalid = |reason: String| Error::InvalidPack {
+            path: path.clone(),
+            reason,
+        };
+        let mut pack: Pack = toml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+
+        if pack.name.is_empty() {
+            pack.name = dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(str::to_owned)
+                .ok_or_else(|| invalid("cannot get the pack name from its directory".to_owned()))?;
+        }
+        pack.root = dir.to_path_buf();
+
+        if let Some(name) = pack
+            .inputs
+            .keys()
+            .find(|name| RESERVED.contains(&name.as_str()))
+        {
+            return Err(invalid(format!("the {name} input is a reserved name")));
+        }
+
+        Ok(pack)
+    }
+}
+
+impl Toolchain for Pack {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn modes(&self) -> &[Mode] {
+        &[Mode::Project]
+    }
+
+    fn inputs(&self) -> Vec<ToolchainInput> {
+        self.inputs
+            .iter()
+            .map(|(name, input)| input.to_toolchain_input(name))
+            .collect()
+    }
+
+    fn plan(&self, request: &GenerationRequest) -> Result<Plan> {
+        let project_path = request.project_path.as_path();
+        let project_name = define_name(&request.name, project_path)?;
+        let licensing = Licensing::parse(&request.license, &request.options.licenses)?;
+        let options = &request.options;
+        let (values, variables) = request.resolve_inputs(&self.name, &self.inputs())?;
+
+        let mut context = variables;
+        context.insert("name".to_owned(), Value::from(project_name));
+        context.insert("branch".to_owned(), Value::from(request.branch.as_str()));
+        context.insert("license_id".to_owned(), Value::from(licensing.expression()));
+
+        let authorship = Authorship::new(request, project_name);
+        authorship.add_context(&mut context);
+
+        let mut recorded_inputs = BTreeMap::from([
+            ("name".to_owned(), project_name.to_owned()),
+            ("license".to_owned(), licensing.expression().to_owned()),
+            ("branch".to_owned(), request.branch.to_owned()),
+        ]);
+        authorship.record(&mut recorded_inputs);
+        recorded_inputs.extend(values);
+
+        let mut env = Environment::new();
+        let mut dirs = vec![project_path.to_path_buf()];
+        let mut files = HashMap::new();
+
+        for dir in &self.dirs {
+            dirs.push(project_path.join(env.render_str(dir, &context).with_template(dir)?));
+        }
+
+        for file in &self.files {
+            let path = project_path.join(
+                env.render_str(&file.path, &context)
+                    .with_template(&file.path)?,
+            );
+            if let Some(parent) = path.parent() {
+                dirs.push(parent.to_path_buf());
+            }
+            files.insert(
+                path,
+                ProjectFile {
+                    template: file.template.to_owned(),
+                    attributes: FileAttributes { mode: file.mode },
+                    component: file.component.clone(),
+                },
+            );
+
+            let source_path = self.root.join(&file.template);
+            let source = read_to_string(&source_path).with_path(&source_path)?;
+            env.add_template_owned(file.template.to_owned(), source)
+                .with_template(&file.template)?;
+        }
+
+        let overrides = user_templates(&self.name, &options.templates_dirs, |file| {
+            self.files
+                .iter()
+                .find(|pack_file| pack_file.template == file)
+                .map(|pack_file| pack_file.template.to_owned())
+        })?;
+        for (name, source) in overrides {
+            env.add_template_owned(name.clone(), source)
+                .with_template(&name)?;
+        }
+
+        let template = SifisTemplate {
+            toolchain: self.name.to_owned(),
+            inputs: recorded_inputs,
+            context,
+            files,
+            dirs,
+            hooks: self.hooks.clone(),
+            env,
+            verbatim: HashMap::new(),
+            authorship,
+        };
+
+        compute_template(template, &licensing, project_path, options)
+    }
+}
+
+/// Loads all the packs contained in the subdirectories of `dirs`.
+///
+/// When two packs have the same name, the one found first is kept.
+pub fn discover_packs(dirs: &[PathBuf]) -> Result<Vec<Pack>> {
+    let mut packs: Vec<Pack> = Vec::new();
+
+    for dir in dirs {
+        if !dir.is_dir() {
+            continue;
+        }
+        let mut entries = read_dir(dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<std::io::Result<Vec<_>>>()
+            })
+            .with_path(dir)?;
+        entries.sort();
+
+        for entry in entries {
+            if !entry.join(PACK_FILE).is_file() {
+                continue;
+            }
+            let pack = Pack::load(&entry)?;
+            if packs.iter().all(|known| known.name != pack.name) {
+                packs.push(pack);
+            }
+        }
+    }
+
+    Ok(packs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, write};
+
+    use super::*;
+    use crate::Registry;
+
+    fn write_pack(dir: &Path, pack: &str, templates: &[(&str, &str)]) {
+        create_dir_all(dir).unwrap();
+        write(dir.join(PACK_FILE), pack).unwrap();
+        for (name, source) in templates {
+            write(dir.join(name), source).unwrap();
+        }
+    }
+
+    #[test]
+    fn name_comes_from_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("go");
+        write_pack(&root, "description = \"Go module\"\n", &[]);
+
+        let pack = Pack::load(&root).unwrap();
+
+        assert_eq!(pack.name, "go");
+        assert_eq!(pack.description, "Go module");
+        assert_eq!(pack.root, root);
+    }
+
+    #[test]
+    fn reserved_inputs_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["name", "license_id", "author", "copyright"] {
+            write_pack(dir.path(), &format!("[inputs.{name}]\n"), &[]);
+
+            assert!(matches!(
+                Pack::load(dir.path()),
+                Err(Error::InvalidPack { reason, .. }) if reason.contains(name)
+            ));
+        }
+    }
+
+    #[test]
+    fn paths_are_rendered() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("go");
+        write_pack(
+            &root,
+            r#"
+dirs = ["cmd/{{ name }}"]
+
+[[files]]
+path = "cmd/{{ name }}/{{ module }}.go"
+template = "main.go"
+mode = 0o755
+
+[inputs.module]
+default = "main"
+"#,
+            &[("main.go", "package {{ module }}\n")],
+        );
+        let pack = Pack::load(&root).unwrap();
+        let request = GenerationRequest::new(dir.path().join("project"));
+
+        let plan = pack.plan(&request).unwrap();
+
+        let cmd = request.project_path.join("cmd/project");
+        assert!(plan.dirs.contains(&cmd));
+        let file = plan
+            .files
+            .iter()
+            .find(|file| file.path == cmd.join("main.go"))
+            .unwrap();
+        assert!(file.content.ends_with("package main"));
+        assert_eq!(file.attributes.mode, Some(0o755));
+    }
+
+    #[test]
+    fn first_pack_found_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let [high, low] = ["high", "low"].map(|name| dir.path().join(name));
+        for (packs_dir, description) in [(&high, "High"), (&low, "Low")] {
+            let pack = format!("description = \"{description}\"\n");
+            write_pack(&packs_dir.join("go"), &pack, &[]);
+            write_pack(&packs_dir.join("cargo"), &pack, &[]);
+        }
+
+        let packs = discover_packs(&[high.clone(), low.clone()]).unwrap();
+        assert_eq!(
+            packs
+                .iter()
+                .map(|pack| (pack.name.as_str(), pack.description.as_str()))
+                .collect::<Vec<_>>(),
+            [("cargo", "High"), ("go", "High")]
+        );
+
+        // The built-in toolchains come before the packs
+        let registry = Registry::with_packs(&[high, low]).unwrap();
+        assert_eq!(
+            registry.find("cargo").unwrap().description(),
+            crate::cargo::Cargo.description()
+        );
+        assert_eq!(registry.find("go").unwrap().description(), "High");
+    }
+}
@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use tracing::warn;
+
+use crate::cargo::Cargo;
+use crate::maven::Maven;
+use crate::meson::Meson;
+use crate::pack::discover_packs;
+use crate::poetry::Poetry;
+use crate::yarn::Yarn;
+use crate::{Error, Result, Toolchain};
+
+/// The toolchains available for a generation.
+#[derive(Default)]
+pub struct Registry {
+    toolchains: Vec<Box<dyn Toolchain>>,
+}
+
+impl Registry {
+    /// Creates a registry holding the built-in toolchains.
+    pub fn builtin() -> Self {
+        Self {
+            toolchains: vec![
+                Box::new(Cargo),
+                Box::new(Maven),
+                Box::new(Meson),
+                Box::new(Poetry),
+                Box::new(Yarn),
+            ],
+        }
+    }
+
+    /// Creates a registry holding the built-in toolchains and the template
+    /// packs contained in the subdirectories of `packs_dirs`.
+    pub fn with_packs(packs_dirs: &[PathBuf]) -> Result<Self> {
+        let mut registry = Self::builtin();
+        for pack in discover_packs(packs_dirs)? {
+            registry.register(Box::new(pack));
+        }
+
+        Ok(registry)
+    }
+
+    /// Adds a toolchain, unless another one has the same name.
+    pub fn register(&mut self, toolchain: Box<dyn Toolchain>) {
+        if self.get(toolchain.name()).is_some() {
+            warn!(
+                "The {} toolchain is already registered, ignoring the new one",
+                toolchain.name()
+            );
+            return;
+        }
+        self.toolchains.push(toolchain);
+    }
+
+    /// Returns the toolchain called `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&dyn Toolchain> {
+        self.toolchains
+            .iter()
+            .find(|toolchain| toolchain.name() == name)
+            .map(Box::as_ref)
+    }
+
+    /// Returns the toolchain called `name`, failing if it does not exist.
+    pub fn find(&self, name: &str) -> Result<&dyn Toolchain> {
+        self.get(name).ok_or_else(|| Error::UnknownToolchain {
+            name: name.to_owned(),
+        })
+    }
+
+    /// Returns the toolchains in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Toolchain> {
+        self.toolchains.iter().map(Box::as_ref)
+    }
+}
@@ -0,0 +1,511 @@
+use std::fs::read_to_string;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::WithPath;
+use crate::manifest::relative_path;
+use crate::upgrade::replay;
+use crate::{
+    hash, Error, FileAttributes, Manifest, Options, Plan, PlannedFile, Result, MANIFEST_FILE,
+};
+
+/// Path of the REUSE metadata in the `dep5` format.
+pub(crate) const DEP5_FILE: &str = ".reuse/dep5";
+
+/// Path of the REUSE metadata in the `REUSE.toml` format.
+pub(crate) const REUSE_TOML_FILE: &str = "REUSE.toml";
+
+// Manifest input recording the format of the REUSE metadata
+pub(crate) const FORMAT_INPUT: &str = "reuse_format";
+
+/// Format of the REUSE metadata of a project.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReuseFormat {
+    /// A `REUSE.toml` file in the project root
+    #[default]
+    Toml,
+    /// A `.reuse/dep5` file, deprecated by the REUSE specification
+    Dep5,
+}
+
+impl ReuseFormat {
+    /// Returns the name of the format as accepted on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReuseFormat::Toml => "toml",
+            ReuseFormat::Dep5 => "dep5",
+        }
+    }
+}
+
+impl FromStr for ReuseFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "toml" => Ok(ReuseFormat::Toml),
+            "dep5" => Ok(ReuseFormat::Dep5),
+            _ => Err(Error::InvalidInput {
+                input: FORMAT_INPUT.to_owned(),
+                value: s.to_owned(),
+                reason: "not a valid REUSE metadata format".to_owned(),
+                suggestion: None,
+            }),
+        }
+    }
+}
+
+/// Licensing information of the files matching some paths, in place of the
+/// one of the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// Paths relative to the project root, where `*` matches any character
+    /// but `/` and `**` matches any character.
+    pub paths: Vec<String>,
+    /// SPDX expression of the licenses of the files.
+    pub license: String,
+    /// Copyright notice of the files, the one of the project when empty.
+    pub copyright: String,
+}
+
+impl Annotation {
+    /// Returns whether the annotation applies to `path`, relative to the
+    /// project root.
+    pub fn matches(&self, path: &str) -> bool {
+        self.paths
+            .iter()
+            .any(|pattern| glob(pattern.as_bytes(), path.as_bytes()))
+    }
+
+    /// Returns the copyright notice of the files, `default` when there is
+    /// none.
+    pub(crate) fn copyright_or<'a>(&'a self, default: &'a str) -> &'a str {
+        if self.copyright.is_empty() {
+            default
+        } else {
+            &self.copyright
+        }
+    }
+}
+
+fn glob(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=path.len()).any(|i| glob(rest, &path[i..])),
+        [b'*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| !path[..i].contains(&b'/'))
+            .any(|i| glob(rest, &path[i..])),
+        [b'\\', c, rest @ ..] | [c, rest @ ..] => path.first() == Some(c) && glob(rest, &path[1..]),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ReuseToml {
+    version: u32,
+    #[serde(rename = "SPDX-PackageName", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(
+        rename = "SPDX-PackageSupplier",
+        skip_serializing_if = "Option::is_none"
+    )]
+    supplier: Option<String>,
+    #[serde(
+        rename = "SPDX-PackageDownloadLocation",
+        skip_serializing_if = "Option::is_none"
+    )]
+    download_location: Option<String>,
+    annotations: Vec<TomlAnnotation>,
+}
+
+#[derive(Debug, Serialize)]
+struct TomlAnnotation {
+    path: Vec<String>,
+    precedence: &'static str,
+    #[serde(
+        rename = "SPDX-FileCopyrightText",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    copyright: Vec<String>,
+    #[serde(
+        rename = "SPDX-License-Identifier",
+        skip_serializing_if = "Option::is_none"
+    )]
+    license: Option<String>,
+}
+
+/// A paragraph of a `dep5` file, as its fields and their lines.
+type Paragraph<'a> = Vec<(&'a str, Vec<&'a str>)>;
+
+fn paragraphs(dep5: &str) -> std::result::Result<Vec<Paragraph<'_>>, String> {
+    let mut paragraphs = vec![Paragraph::new()];
+    for (number, line) in dep5.lines().enumerate() {
+        if line.trim().is_empty() {
+            if !paragraphs.last().is_some_and(Vec::is_empty) {
+                paragraphs.push(Paragraph::new());
+            }
+        } else if line.starts_with([' ', '\t']) {
+            let (_, lines) = paragraphs
+                .last_mut()
+                .and_then(|paragraph| paragraph.last_mut())
+                .ok_or_else(|| format!("line {} continues no field", number + 1))?;
+            // A lone dot stands for an empty line
+            lines.push(match line.trim() {
+                "." => "",
+                line => line,
+            });
+        } else {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("line {} is not a field", number + 1))?;
+            let value = value.trim();
+            let lines = if value.is_empty() {
+                vec![]
+            } else {
+                vec![value]
+            };
+            if let Some(paragraph) = paragraphs.last_mut() {
+                paragraph.push((name.trim(), lines));
+            }
+        }
+    }
+    paragraphs.retain(|paragraph| !paragraph.is_empty());
+
+    Ok(paragraphs)
+}
+
+fn field<'p>(paragraph: &'p Paragraph<'_>, name: &str) -> Option<&'p [&'p str]> {
+    paragraph
+        .iter()
+        .find(|(field, _)| field.eq_ignore_ascii_case(name))
+        .map(|(_, lines)| lines.as_slice())
+}
+
+// Turns a path of a `dep5` file into a `REUSE.toml` one
+fn dep5_pattern(pattern: &str) -> String {
+    let mut path = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '*' {
+            while chars.next_if_eq(&'*').is_some() {}
+            path.push_str("**");
+        } else {
+            path.push(c);
+        }
+    }
+    path
+}
+
+/// Converts the content of a `.reuse/dep5` file into a `REUSE.toml` one.
+///
+/// The `*` of the `dep5` paths become `**`, since they match `/` as well, and
+/// the annotations are aggregated with the information found in the files,
+/// as the `dep5` ones are.
+pub(crate) fn dep5_to_toml(dep5: &str) -> std::result::Result<String, String> {
+    let mut paragraphs = paragraphs(dep5)?.into_iter();
+    let header = paragraphs
+        .next()
+        .filter(|paragraph| field(paragraph, "Format").is_some())
+        .ok_or("the header paragraph is missing")?;
+    let first_line = |name: &str| {
+        field(&header, name).and_then(|lines| lines.first().map(|&line| line.to_owned()))
+    };
+
+    let mut annotations = Vec::new();
+    for paragraph in paragraphs {
+        let Some(files) = field(&paragraph, "Files") else {
+            // Stand-alone license paragraphs carry only license texts
+            continue;
+        };
+        let path = files
+            .iter()
+            .flat_map(|line| line.split_whitespace())
+            .map(dep5_pattern)
+            .collect();
+        annotations.push(TomlAnnotation {
+            path,
+            precedence: "aggregate",
+            copyright: field(&paragraph, "Copyright")
+                .unwrap_or_default()
+                .iter()
+                .filter(|line| !line.is_empty())
+                .map(|line| line.to_string())
+                .collect(),
+            // The next lines of the field hold the license text
+            license: field(&paragraph, "License")
+                .and_then(|lines| lines.first())
+                .map(|line| line.to_string()),
+        });
+    }
+
+    let reuse = ReuseToml {
+        version: 1,
+        name: first_line("Upstream-Name"),
+        supplier: first_line("Upstream-Contact"),
+        download_location: first_line("Source"),
+        annotations,
+    };
+
+    toml::to_string(&reuse).map_err(|e| e.to_string())
+}
+
+/// Computes the replacement of the `.reuse/dep5` file of a project with the
+/// equivalent `REUSE.toml` one.
+///
+/// The plan writes `REUSE.toml` and removes `.reuse/dep5`. When the project
+/// has a manifest, it is updated as well, so that `upgrade` and `check`
+/// render `REUSE.toml` from then on, and a `dep5` file which was never
+/// edited is replaced with the rendered `REUSE.toml` template, using the
+/// user templates and the custom licenses in `options`.
+pub fn migrate_dep5(project_path: &Path, options: &Options) -> Result<Plan> {
+    let dep5_path = project_path.join(DEP5_FILE);
+    let toml_path = project_path.join(REUSE_TOML_FILE);
+    if toml_path.exists() {
+        return Err(Error::Conflicts {
+            paths: vec![toml_path],
+        });
+    }
+
+    let dep5 = read_to_string(&dep5_path).with_path(&dep5_path)?;
+    let mut content = dep5_to_toml(&dep5).map_err(|reason| Error::Dep5 {
+        path: dep5_path.clone(),
+        reason,
+    })?;
+
+    let mut plan = Plan {
+        root: project_path.to_path_buf(),
+        removed: vec![dep5_path],
+        ..Default::default()
+    };
+
+    let manifest_path = project_path.join(MANIFEST_FILE);
+    if manifest_path.exists() {
+        let mut manifest = Manifest::read(project_path)?;
+        let pristine = manifest.files.remove(DEP5_FILE) == Some(hash(dep5.as_bytes()));
+        manifest.templates.remove("dep5.reuse");
+        manifest.inputs.insert(
+            FORMAT_INPUT.to_owned(),
+            ReuseFormat::Toml.as_str().to_owned(),
+        );
+
+        if pristine {
+            let replayed = replay(&manifest, project_path, options)?;
+            let rendered = |name: &str| {
+                replayed
+                    .files
+                    .iter()
+                    .find(|file| relative_path(&file.path, project_path) == name)
+            };
+            if let Some(file) = rendered(REUSE_TOML_FILE) {
+                content = file.content.clone();
+                manifest
+                    .files
+                    .insert(REUSE_TOML_FILE.to_owned(), hash(content.as_bytes()));
+                // The template is recorded as the generation would
+                if let Some(replayed) = rendered(MANIFEST_FILE) {
+                    let replayed = Manifest::parse(&replayed.content, replayed.path.clone())?;
+                    if let Some(source) = replayed.templates.get(&file.template) {
+                        manifest
+                            .templates
+                            .insert(file.template.clone(), source.clone());
+                    }
+                }
+            }
+        }
+
+        // The license header of the manifest is kept
+        let current = read_to_string(&manifest_path).with_path(&manifest_path)?;
+        let header: String = current
+            .lines()
+            .take_while(|line| line.starts_with('#'))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let mut updated = manifest.to_toml()?;
+        if !header.is_empty() {
+            updated = format!("{header}\n{updated}");
+        }
+        plan.files.push(PlannedFile {
+            path: manifest_path,
+            template: String::new(),
+            content: updated,
+            attributes: FileAttributes::default(),
+            component: None,
+        });
+    }
+
+    plan.files.push(PlannedFile {
+        path: toml_path,
+        template: String::new(),
+        content,
+        attributes: FileAttributes::default(),
+        component: None,
+    });
+    plan.files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEP5: &str = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Upstream-Name: project
+Upstream-Contact: Jane Doe <jane@example.com>
+Source: https://example.com/project
+
+Files: *
+Copyright: 2024 Jane Doe
+ 2025 John Doe
+License: MIT
+
+Files: assets/*.png
+ docs/*
+Copyright: 2023 Artist
+License: CC-BY-4.0
+ Attribution 4.0 International
+ .
+ The text of the license.
+
+License: LicenseRef-Stand-Alone
+ The text of a license not bound to any file.
+";
+
+    #[test]
+    fn glob_star_stops_at_slashes() {
+        assert!(glob(b"*.rs", b"main.rs"));
+        assert!(!glob(b"*.rs", b"src/main.rs"));
+        assert!(glob(b"src/*.rs", b"src/main.rs"));
+        assert!(!glob(b"src/*", b"src/bin/main.rs"));
+    }
+
+    #[test]
+    fn glob_double_star_crosses_slashes() {
+        assert!(glob(b"**", b"src/bin/main.rs"));
+        assert!(glob(b"**.rs", b"src/bin/main.rs"));
+        assert!(glob(b"src/**", b"src/bin/main.rs"));
+        assert!(!glob(b"src/**", b"tests/main.rs"));
+    }
+
+    #[test]
+    fn glob_escapes_characters() {
+        assert!(glob(br"file\*.txt", b"file*.txt"));
+        assert!(!glob(br"file\*.txt", b"file1.txt"));
+        assert!(glob(br"dir\\file", br"dir\file"));
+    }
+
+    #[test]
+    fn annotation_matches_any_path() {
+        let annotation = Annotation {
+            paths: vec!["docs/**".to_owned(), "*.md".to_owned()],
+            license: "CC0-1.0".to_owned(),
+            copyright: String::new(),
+        };
+
+        assert!(annotation.matches("docs/guide/index.html"));
+        assert!(annotation.matches("README.md"));
+        assert!(!annotation.matches("src/README.md"));
+        assert_eq!(annotation.copyright_or("holder"), "holder");
+    }
+
+    #[test]
+    fn paragraphs_join_continuation_lines() {
+        let paragraphs = paragraphs(DEP5).unwrap();
+
+        assert_eq!(paragraphs.len(), 4);
+        assert_eq!(
+            field(&paragraphs[1], "copyright").unwrap(),
+            ["2024 Jane Doe", "2025 John Doe"]
+        );
+        assert_eq!(
+            field(&paragraphs[2], "Files").unwrap(),
+            ["assets/*.png", "docs/*"]
+        );
+        // The lone dot is an empty line
+        assert_eq!(
+            field(&paragraphs[2], "License").unwrap(),
+            [
+                "CC-BY-4.0",
+                "Attribution 4.0 International",
+                "",
+                "The text of the license."
+            ]
+        );
+    }
+
+    #[test]
+    fn paragraphs_reject_malformed_lines() {
+        assert_eq!(
+            paragraphs(" continued").unwrap_err(),
+            "line 1 continues no field"
+        );
+        assert_eq!(
+            paragraphs("Files: *\nnot a field").unwrap_err(),
+            "line 2 is not a field"
+        );
+    }
+
+    #[test]
+    fn dep5_stars_become_double_stars() {
+        assert_eq!(dep5_pattern("*"), "**");
+        assert_eq!(dep5_pattern("docs/*.md"), "docs/**.md");
+        assert_eq!(dep5_pattern("src/**/*.rs"), "src/**/**.rs");
+        assert_eq!(dep5_pattern("README.md"), "README.md");
+    }
+
+    #[test]
+    fn dep5_is_converted() {
+        let toml = dep5_to_toml(DEP5).unwrap();
+
+        assert_eq!(
+            toml,
+            r#"version = 1
+SPDX-PackageName = "project"
+SPDX-PackageSupplier = "Jane Doe <jane@example.com>"
+SPDX-PackageDownloadLocation = "https://example.com/project"
+
+[[annotations]]
+path = ["**"]
+precedence = "aggregate"
+SPDX-FileCopyrightText = ["2024 Jane Doe", "2025 John Doe"]
+SPDX-License-Identifier = "MIT"
+
+[[annotations]]
+path = ["assets/**.png", "docs/**"]
+precedence = "aggregate"
+SPDX-FileCopyrightText = ["2023 Artist"]
+SPDX-License-Identifier = "CC-BY-4.0"
+"#
+        );
+    }
+
+    #[test]
+    fn dep5_without_header_is_rejected() {
+        let dep5 = "Files: *\nCopyright: 2024 Jane Doe\nLicense: MIT\n";
+
+        assert_eq!(
+            dep5_to_toml(dep5).unwrap_err(),
+            "the header paragraph is missing"
+        );
+    }
+
+    #[test]
+    fn migration_removes_dep5() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".reuse")).unwrap();
+        std::fs::write(dir.path().join(DEP5_FILE), DEP5).unwrap();
+
+        let plan = migrate_dep5(dir.path(), &Options::default()).unwrap();
+
+        assert_eq!(plan.removed, [dir.path().join(DEP5_FILE)]);
+        assert_eq!(plan.files.len(), 1);
+        assert_eq!(plan.files[0].path, dir.path().join(REUSE_TOML_FILE));
+
+        plan.write(crate::ConflictPolicy::Abort).unwrap();
+        assert!(!dir.path().join(".reuse").exists());
+        assert!(dir.path().join(REUSE_TOML_FILE).exists());
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, write};
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tracing::debug;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::error::WithPath;
+use crate::manifest::relative_path;
+use crate::{BoxError, Error, FileAttributes, Result};
+
+/// Destination of the directories and files of a [`Plan`](crate::Plan).
+pub trait Sink {
+    /// Creates a directory and all its missing parents.
+    fn create_dir(&mut self, path: &Path) -> Result<()>;
+
+    /// Writes a file and applies its attributes, its parent directory has
+    /// already been created.
+    fn write_file(&mut self, path: &Path, content: &[u8], attributes: FileAttributes)
+        -> Result<()>;
+}
+
+/// Writes to the real filesystem.
+#[derive(Debug, Default)]
+pub struct FsSink;
+
+impl Sink for FsSink {
+    fn create_dir(&mut self, path: &Path) -> Result<()> {
+        debug!("Creating {}", path.display());
+        create_dir_all(path).with_path(path)
+    }
+
+    fn write_file(
+        &mut self,
+        path: &Path,
+        content: &[u8],
+        attributes: FileAttributes,
+    ) -> Result<()> {
+        debug!("Creating {}", path.display());
+        write(path, content).with_path(path)?;
+
+        #[cfg(unix)]
+        if let Some(mode) = attributes.mode {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+                .with_path(path)?;
+        }
+        #[cfg(not(unix))]
+        let _ = attributes;
+
+        Ok(())
+    }
+}
+
+/// Keeps the directories and files in memory.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemorySink {
+    /// Directories created, sorted.
+    pub dirs: Vec<PathBuf>,
+    /// Files written, keyed by path.
+    pub files: BTreeMap<PathBuf, Vec<u8>>,
+    /// Attributes of the files written, keyed by path.
+    pub attributes: BTreeMap<PathBuf, FileAttributes>,
+}
+
+impl Sink for MemorySink {
+    fn create_dir(&mut self, path: &Path) -> Result<()> {
+        if let Err(pos) = self.dirs.binary_search_by(|dir| dir.as_path().cmp(path)) {
+            self.dirs.insert(pos, path.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn write_file(
+        &mut self,
+        path: &Path,
+        content: &[u8],
+        attributes: FileAttributes,
+    ) -> Result<()> {
+        self.files.insert(path.to_path_buf(), content.to_vec());
+        self.attributes.insert(path.to_path_buf(), attributes);
+        Ok(())
+    }
+}
+
+fn archive_error(path: &Path, source: impl Into<BoxError>) -> Error {
+    Error::Archive {
+        path: path.to_path_buf(),
+        source: source.into(),
+    }
+}
+
+/// Writes a `.tar.gz` archive, storing the paths relative to a root.
+pub struct TarGzSink<W: Write> {
+    builder: tar::Builder<GzEncoder<W>>,
+    root: PathBuf,
+}
+
+impl<W: Write> TarGzSink<W> {
+    /// Creates a new archive, the entries are stored relative to `root`.
+    pub fn new(writer: W, root: &Path) -> Self {
+        Self {
+            builder: tar::Builder::new(GzEncoder::new(writer, Compression::default())),
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// Completes the archive and returns the underlying writer.
+    pub fn finish(self) -> Result<W> {
+        self.builder
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .map_err(|e| archive_error(&self.root, e))
+    }
+
+    fn header(entry_type: tar::EntryType, mode: u32, size: u64) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_mode(mode);
+        header.set_size(size);
+        header
+    }
+}
+
+impl<W: Write> Sink for TarGzSink<W> {
+    fn create_dir(&mut self, path: &Path) -> Result<()> {
+        let name = relative_path(path, &self.root);
+        if name.is_empty() {
+            return Ok(());
+        }
+        let mut header = Self::header(tar::EntryType::Directory, 0o755, 0);
+        self.builder
+            .append_data(&mut header, format!("{name}/"), std::io::empty())
+            .map_err(|e| archive_error(path, e))
+    }
+
+    fn write_file(
+        &mut self,
+        path: &Path,
+        content: &[u8],
+        attributes: FileAttributes,
+    ) -> Result<()> {
+        let name = relative_path(path, &self.root);
+        let mode = attributes.mode.unwrap_or(0o644);
+        let mut header = Self::header(tar::EntryType::Regular, mode, content.len() as u64);
+        self.builder
+            .append_data(&mut header, name, content)
+            .map_err(|e| archive_error(path, e))
+    }
+}
+
+/// Writes a `.zip` archive, storing the paths relative to a root.
+pub struct ZipSink<W: Write + Seek> {
+    writer: ZipWriter<W>,
+    root: PathBuf,
+}
+
+impl<W: Write + Seek> ZipSink<W> {
+    /// Creates a new archive, the entries are stored relative to `root`.
+    pub fn new(writer: W, root: &Path) -> Self {
+        Self {
+            writer: ZipWriter::new(writer),
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// Completes the archive and returns the underlying writer.
+    pub fn finish(self) -> Result<W> {
+        self.writer
+            .finish()
+            .map_err(|e| archive_error(&self.root, e))
+    }
+}
+
+impl<W: Write + Seek> Sink for ZipSink<W> {
+    fn create_dir(&mut self, path: &Path) -> Result<()> {
+        let name = relative_path(path, &self.root);
+        if name.is_empty() {
+            return Ok(());
+        }
+        self.writer
+            .add_directory(name, SimpleFileOptions::default())
+            .map_err(|e| archive_error(path, e))
+    }
+
+    fn write_file(
+        &mut self,
+        path: &Path,
+        content: &[u8],
+        attributes: FileAttributes,
+    ) -> Result<()> {
+        let name = relative_path(path, &self.root);
+        let mut options = SimpleFileOptions::default();
+        if let Some(mode) = attributes.mode {
+            options = options.unix_permissions(mode);
+        }
+        self.writer
+            .start_file(name, options)
+            .map_err(|e| archive_error(path, e))?;
+        self.writer
+            .write_all(content)
+            .map_err(|e| archive_error(path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::*;
+    use crate::{GenerationRequest, Plan, Registry};
+
+    fn plan() -> Plan {
+        let request = GenerationRequest::new("/tmp/project");
+        Registry::builtin()
+            .find("meson")
+            .unwrap()
+            .plan(&request)
+            .unwrap()
+    }
+
+    // The files of the plan, relative to its root
+    fn file_names(plan: &Plan) -> Vec<String> {
+        plan.files
+            .iter()
+            .map(|file| relative_path(&file.path, &plan.root))
+            .collect()
+    }
+
+    #[test]
+    fn memory_sink_keeps_the_plan() {
+        let plan = plan();
+        let mut sink = MemorySink::default();
+
+        plan.write_to(&mut sink).unwrap();
+
+        assert_eq!(sink.dirs, plan.dirs);
+        assert_eq!(
+            sink.files.keys().cloned().collect::<Vec<_>>(),
+            plan.files
+                .iter()
+                .map(|file| file.path.clone())
+                .collect::<Vec<_>>()
+        );
+        let script = plan.root.join("run_tests.sh");
+        assert_eq!(sink.attributes[&script].mode, Some(0o755));
+    }
+
+    #[test]
+    fn tar_gz_sink_stores_relative_paths() {
+        let plan = plan();
+        let mut sink = TarGzSink::new(Vec::new(), &plan.root);
+        plan.write_to(&mut sink).unwrap();
+        let archive = sink.finish().unwrap();
+
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(archive.as_slice()));
+        let mut files = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let entry = entry.unwrap();
+            let header = entry.header();
+            let name = entry.path().unwrap().to_string_lossy().into_owned();
+            if header.entry_type() != tar::EntryType::Regular {
+                continue;
+            }
+            if name == "run_tests.sh" {
+                assert_eq!(header.mode().unwrap(), 0o755);
+            }
+            files.push(name);
+        }
+
+        assert_eq!(files, file_names(&plan));
+    }
+
+    #[test]
+    fn zip_sink_stores_relative_paths() {
+        let plan = plan();
+        let mut sink = ZipSink::new(Cursor::new(Vec::new()), &plan.root);
+        plan.write_to(&mut sink).unwrap();
+        let archive = sink.finish().unwrap();
+
+        let mut archive = zip::ZipArchive::new(archive).unwrap();
+        let mut files = Vec::new();
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).unwrap();
+            if entry.is_dir() {
+                continue;
+            }
+            if entry.name() == "run_tests.sh" {
+                assert_eq!(entry.unix_mode().unwrap() & 0o777, 0o755);
+            }
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+            assert!(!content.is_empty());
+            files.push(entry.name().to_owned());
+        }
+
+        assert_eq!(files, file_names(&plan));
+    }
+}
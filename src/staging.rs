@@ -0,0 +1,257 @@
+use std::fs::{create_dir, remove_dir, remove_file, rename};
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, info, warn};
+
+use crate::error::WithPath;
+use crate::{ConflictPolicy, FsSink, Plan, Result, Sink};
+
+/// Changes applied to the project directory, reverted on failure.
+#[derive(Default)]
+struct Transaction {
+    created_dirs: Vec<PathBuf>,
+    moved: Vec<PathBuf>,
+    // Target path and where its previous content has been moved
+    saved: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Transaction {
+    fn create_dir(&mut self, dir: &Path) -> Result<()> {
+        let missing: Vec<&Path> = dir
+            .ancestors()
+            .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+            .collect();
+
+        for ancestor in missing.into_iter().rev() {
+            debug!("Creating {}", ancestor.display());
+            create_dir(ancestor).with_path(ancestor)?;
+            self.created_dirs.push(ancestor.to_path_buf());
+        }
+
+        Ok(())
+    }
+
+    fn save(&mut self, target: &Path, saved: PathBuf) -> Result<()> {
+        rename(target, &saved).with_path(target)?;
+        self.saved.push((target.to_path_buf(), saved));
+        Ok(())
+    }
+
+    fn move_into_place(&mut self, staged: &Path, target: &Path) -> Result<()> {
+        debug!("Creating {}", target.display());
+        rename(staged, target).with_path(target)?;
+        self.moved.push(target.to_path_buf());
+        Ok(())
+    }
+
+    fn rollback(self) {
+        for path in self.moved.iter().rev() {
+            if let Err(e) = remove_file(path) {
+                warn!("Cannot remove {}: {e}", path.display());
+            }
+        }
+        for (target, saved) in self.saved.iter().rev() {
+            if let Err(e) = rename(saved, target) {
+                warn!("Cannot restore {}: {e}", target.display());
+            }
+        }
+        for dir in self.created_dirs.iter().rev() {
+            let _ = remove_dir(dir);
+        }
+    }
+}
+
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".orig");
+    path.with_file_name(name)
+}
+
+// The existing directory closest to the project, outside of it, so that the
+// staging directory never ends up in the repository of the user
+fn staging_anchor(root: &Path) -> PathBuf {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    root.parent()
+        .and_then(|parent| parent.ancestors().find(|ancestor| ancestor.is_dir()))
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
+
+/// Writes a plan through a staging directory created next to the project.
+///
+/// Every file is written in the staging directory first, then they are all
+/// moved into place and the removed files are moved out of the project.
+/// If any step fails, the project directory is restored to its previous
+/// state.
+pub(crate) fn write(plan: &Plan, policy: ConflictPolicy, conflicts: &[&Path]) -> Result<()> {
+    let anchor = staging_anchor(&plan.root);
+    let staging = tempfile::Builder::new()
+        .prefix(".sifis-generate-")
+        .tempdir_in(&anchor)
+        .with_path(&anchor)?;
+    let staged_dir = staging.path().join("files");
+    let saved_dir = staging.path().join("saved");
+
+    // Stage every file
+    let mut sink = FsSink;
+    sink.create_dir(&staged_dir)?;
+    sink.create_dir(&saved_dir)?;
+    let mut staged = Vec::new();
+    for (index, file) in plan.files.iter().enumerate() {
+        let conflict = conflicts.contains(&file.path.as_path());
+        if conflict && policy == ConflictPolicy::Skip {
+            info!("Skipping {}", file.path.display());
+            continue;
+        }
+        let path = staged_dir.join(index.to_string());
+        sink.write_file(&path, file.content.as_bytes(), file.attributes)?;
+        staged.push((index, path, file.path.as_path(), conflict));
+    }
+
+    // Move everything into place
+    let mut transaction = Transaction::default();
+    let result = (|| {
+        for dir in &plan.dirs {
+            transaction.create_dir(dir)?;
+        }
+
+        for (index, path, target, conflict) in staged {
+            if conflict {
+                if policy == ConflictPolicy::Backup {
+                    let backup = backup_path(target);
+                    info!("Backing up {} to {}", target.display(), backup.display());
+                    transaction.save(target, backup)?;
+                } else {
+                    transaction.save(target, saved_dir.join(index.to_string()))?;
+                }
+            }
+            transaction.move_into_place(&path, target)?;
+        }
+
+        // The removed files are dropped along with the staging directory
+        for (index, path) in plan.removed.iter().enumerate() {
+            debug!("Removing {}", path.display());
+            transaction.save(path, saved_dir.join(format!("removed-{index}")))?;
+        }
+
+        Ok(())
+    })();
+
+    if result.is_err() {
+        transaction.rollback();
+    }
+
+    // The directories left empty by the removed files are removed as well
+    if result.is_ok() {
+        for dir in plan.removed.iter().filter_map(|path| path.parent()) {
+            let _ = remove_dir(dir);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{read_dir, read_to_string, write};
+
+    use super::*;
+    use crate::{Error, FileAttributes, PlannedFile};
+
+    fn file(path: PathBuf, content: &str) -> PlannedFile {
+        PlannedFile {
+            path,
+            template: String::new(),
+            content: content.to_owned(),
+            attributes: FileAttributes::default(),
+            component: None,
+        }
+    }
+
+    // A plan overwriting `existing`, creating `dir/new` and failing on a
+    // file inside `blocker`, which is not a directory
+    fn failing_plan(root: &Path) -> Plan {
+        write(root.join("existing"), "old").unwrap();
+        write(root.join("blocker"), "").unwrap();
+
+        Plan {
+            root: root.to_path_buf(),
+            dirs: vec![root.join("dir")],
+            files: vec![
+                file(root.join("dir/new"), "new"),
+                file(root.join("existing"), "new"),
+                file(root.join("blocker/file"), "new"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn entries(root: &Path) -> Vec<String> {
+        let mut entries: Vec<String> = read_dir(root)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn failure_restores_the_project() {
+        for policy in [ConflictPolicy::Overwrite, ConflictPolicy::Backup] {
+            let dir = tempfile::tempdir().unwrap();
+            let plan = failing_plan(dir.path());
+
+            assert!(plan.write(policy).is_err());
+            assert_eq!(read_to_string(dir.path().join("existing")).unwrap(), "old");
+            assert_eq!(entries(dir.path()), ["blocker", "existing"]);
+        }
+    }
+
+    #[test]
+    fn backup_keeps_the_previous_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("existing");
+        write(&existing, "old").unwrap();
+        let plan = Plan {
+            root: dir.path().to_path_buf(),
+            files: vec![file(existing.clone(), "new")],
+            ..Default::default()
+        };
+
+        plan.write(ConflictPolicy::Backup).unwrap();
+
+        assert_eq!(read_to_string(&existing).unwrap(), "new");
+        assert_eq!(read_to_string(backup_path(&existing)).unwrap(), "old");
+    }
+
+    #[test]
+    fn backup_never_replaces_an_earlier_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("existing");
+        write(&existing, "old").unwrap();
+        write(backup_path(&existing), "older").unwrap();
+        let plan = Plan {
+            root: dir.path().to_path_buf(),
+            files: vec![file(existing.clone(), "new")],
+            ..Default::default()
+        };
+
+        let error = plan.write(ConflictPolicy::Backup).unwrap_err();
+
+        assert!(matches!(error, Error::Conflicts { paths } if paths == [backup_path(&existing)]));
+        assert_eq!(read_to_string(&existing).unwrap(), "old");
+        assert_eq!(read_to_string(backup_path(&existing)).unwrap(), "older");
+    }
+
+    #[test]
+    fn staging_is_outside_of_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let parent = dir.path().canonicalize().unwrap();
+        let root = parent.join("project");
+
+        assert_eq!(staging_anchor(&root.join("missing")), parent);
+        std::fs::create_dir(&root).unwrap();
+        assert_eq!(staging_anchor(&root), parent);
+        assert_eq!(staging_anchor(&root.join(".")), parent);
+    }
+}
@@ -1,14 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
 use minijinja::value::Value;
 
+use crate::naming;
 use crate::{
-    builtin_templates, compute_template, define_license, define_name, BuildTemplate, CreateCi,
+    builtin_templates, generate, BuildTemplate, BuiltinTemplate, GenerationRequest, Mode, Plan,
+    Result, TemplateFile, Toolchain, ToolchainInput,
 };
 
-static CARGO_TEMPLATES: &[(&str, &str)] = &builtin_templates!["cargo" =>
+static CARGO_TEMPLATES: &[BuiltinTemplate] = &builtin_templates!["cargo" =>
     ("md.README", "README.md"),
     ("ci.github", "github.yml"),
     ("ci.github.deploy", "github-deploy.yml"),
@@ -20,35 +21,46 @@ static CARGO_TEMPLATES: &[(&str, &str)] = &builtin_templates!["cargo" =>
     ("fuzz.target", "fuzz_target_1.rs")
 ];
 
-/// A cargo project data.
-#[derive(Default)]
-pub struct Cargo<'a>(&'a str);
+/// The CI configuration of a cargo project.
+#[derive(Debug, Default)]
+pub struct Cargo;
 
-impl<'a> CreateCi for Cargo<'a> {
-    fn create_ci(
-        &self,
-        project_name: &str,
-        project_path: &Path,
-        license: &str,
-        github_branch: &str,
-    ) -> Result<()> {
-        let project_name = define_name(project_name, project_path)?;
-        let license = define_license(license)?;
-        let template = self.build(project_path, project_name, license.id(), github_branch);
-        compute_template(template, license, project_path)
+impl Toolchain for Cargo {
+    fn name(&self) -> &str {
+        "cargo"
+    }
+
+    fn description(&self) -> &str {
+        "Generate a CI for a cargo project"
     }
-}
 
-impl<'a> Cargo<'a> {
-    /// Creates a new `Cargo` instance.
-    pub fn new(docker_image_description: &'a str) -> Self {
-        Self(docker_image_description)
+    fn modes(&self) -> &[Mode] {
+        &[Mode::Ci]
     }
 
+    fn inputs(&self) -> Vec<ToolchainInput> {
+        vec![ToolchainInput {
+            name: "docker_image_description".to_owned(),
+            description: "Docker image description".to_owned(),
+            component: Some("docker".to_owned()),
+            ..Default::default()
+        }]
+    }
+
+    fn templates(&self) -> &'static [BuiltinTemplate] {
+        CARGO_TEMPLATES
+    }
+
+    fn plan(&self, request: &GenerationRequest) -> Result<Plan> {
+        generate(self, request)
+    }
+}
+
+impl Cargo {
     fn project_structure(
         project_path: &Path,
         name: &str,
-    ) -> (HashMap<PathBuf, &'static str>, Vec<PathBuf>) {
+    ) -> (HashMap<PathBuf, TemplateFile>, Vec<PathBuf>) {
         let root = project_path.to_path_buf();
         let github = project_path.join(".github/workflows");
         let docker = project_path.join("docker");
@@ -58,24 +70,48 @@ impl<'a> Cargo<'a> {
         let mut template_files = HashMap::new();
 
         // README
-        template_files.insert(root.join("README.md"), "md.README");
+        template_files.insert(
+            root.join("README.md"),
+            TemplateFile::from("md.README").component("readme"),
+        );
 
         // Continuous Integration
-        template_files.insert(github.join(format!("{name}.yml")), "ci.github");
+        template_files.insert(
+            github.join(format!("{name}.yml")),
+            TemplateFile::from("ci.github").component("ci"),
+        );
         template_files.insert(
             github.join(format!("{name}-docker-application.yml")),
-            "ci.github.docker",
+            TemplateFile::from("ci.github.docker").component("docker"),
+        );
+        template_files.insert(
+            github.join("deploy.yml"),
+            TemplateFile::from("ci.github.deploy").component("deploy"),
         );
-        template_files.insert(github.join("deploy.yml"), "ci.github.deploy");
 
         // Docker
-        template_files.insert(docker.join("Dockerfile-amd64"), "docker.amd64");
-        template_files.insert(docker.join("Dockerfile-arm64"), "docker.arm64");
+        template_files.insert(
+            docker.join("Dockerfile-amd64"),
+            TemplateFile::from("docker.amd64").component("docker"),
+        );
+        template_files.insert(
+            docker.join("Dockerfile-arm64"),
+            TemplateFile::from("docker.arm64").component("docker"),
+        );
 
         // Fuzz
-        template_files.insert(fuzz.join(".gitignore"), "fuzz.gitignore");
-        template_files.insert(fuzz.join("Cargo.toml"), "fuzz.cargo");
-        template_files.insert(fuzz_targets.join("fuzz_target_1.rs"), "fuzz.target");
+        template_files.insert(
+            fuzz.join(".gitignore"),
+            TemplateFile::from("fuzz.gitignore").component("fuzz"),
+        );
+        template_files.insert(
+            fuzz.join("Cargo.toml"),
+            TemplateFile::from("fuzz.cargo").component("fuzz"),
+        );
+        template_files.insert(
+            fuzz_targets.join("fuzz_target_1.rs"),
+            TemplateFile::from("fuzz.target").component("fuzz"),
+        );
 
         (
             template_files,
@@ -84,26 +120,27 @@ impl<'a> Cargo<'a> {
     }
 }
 
-impl<'a> BuildTemplate for Cargo<'a> {
+impl BuildTemplate for Cargo {
     fn define(
         &self,
         project_path: &Path,
         project_name: &str,
         license: &str,
         github_branch: &str,
+        _inputs: &BTreeMap<String, String>,
     ) -> (
-        HashMap<PathBuf, &'static str>,
+        HashMap<PathBuf, TemplateFile>,
         Vec<PathBuf>,
         HashMap<&'static str, Value>,
     ) {
         let mut context = HashMap::new();
 
-        context.insert("name", Value::from_serializable(&project_name));
-        context.insert("branch", Value::from_serializable(&github_branch));
-        context.insert("license_id", Value::from_serializable(&license));
+        context.insert("name", Value::from_serialize(project_name));
+        context.insert("branch", Value::from_serialize(github_branch));
+        context.insert("license_id", Value::from_serialize(license));
         context.insert(
-            "docker_image_description",
-            Value::from_serializable(&self.0),
+            "crate_name",
+            Value::from_serialize(naming::crate_name(project_name)),
         );
 
         let (files, dirs) = Cargo::project_structure(project_path, project_name);
@@ -111,7 +148,7 @@ impl<'a> BuildTemplate for Cargo<'a> {
         (files, dirs, context)
     }
 
-    fn get_templates() -> &'static [(&'static str, &'static str)] {
-        CARGO_TEMPLATES
+    fn check_name(&self, project_name: &str, _inputs: &BTreeMap<String, String>) -> Result<()> {
+        naming::check_crate(self.name(), project_name)
     }
 }
@@ -1,14 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
 use minijinja::value::Value;
 
+use crate::naming;
 use crate::{
-    builtin_templates, compute_template, define_license, define_name, BuildTemplate, CreateProject,
+    builtin_templates, generate, BuildTemplate, BuiltinTemplate, GenerationRequest, Mode, Plan,
+    Result, TemplateFile, Toolchain, ToolchainInput,
 };
 
-static MAVEN_TEMPLATES: &[(&str, &str)] = &builtin_templates!["maven" =>
+static MAVEN_TEMPLATES: &[BuiltinTemplate] = &builtin_templates!["maven" =>
     ("java.entry", "Entry.java"),
     ("java.example", "Example.java"),
     ("xml.pom", "pom.xml"),
@@ -21,90 +22,118 @@ const MAIN: &str = "main/java";
 const TESTS: &str = "test/java";
 
 /// A maven project.
-pub struct Maven<'a>(&'a str);
+#[derive(Debug, Default)]
+pub struct Maven;
 
-impl<'a> CreateProject for Maven<'a> {
-    fn create_project(
-        &self,
-        project_name: &str,
-        project_path: &Path,
-        license: &str,
-        github_branch: &str,
-    ) -> Result<()> {
-        let project_name = define_name(project_name, project_path)?;
-        let license = define_license(license)?;
-        let (project_path, project_name) = if let Some(parent) = project_path.parent() {
-            (parent.join(project_name), project_name)
-        } else {
-            (Path::new(project_name).to_path_buf(), project_name)
-        };
-        let template = self.build(&project_path, project_name, license.id(), github_branch);
-        compute_template(template, license, &project_path)
+impl Toolchain for Maven {
+    fn name(&self) -> &str {
+        "maven"
+    }
+
+    fn description(&self) -> &str {
+        "Generate a new maven project"
+    }
+
+    fn modes(&self) -> &[Mode] {
+        &[Mode::Project]
     }
-}
 
-impl<'a> Maven<'a> {
-    /// Creates a new `Maven` instance.
-    pub fn new(group: &'a str) -> Self {
-        Self(group)
+    fn inputs(&self) -> Vec<ToolchainInput> {
+        vec![ToolchainInput {
+            name: "group".to_owned(),
+            description: "Java group".to_owned(),
+            positional: true,
+            ..Default::default()
+        }]
     }
 
+    fn templates(&self) -> &'static [BuiltinTemplate] {
+        MAVEN_TEMPLATES
+    }
+
+    fn plan(&self, request: &GenerationRequest) -> Result<Plan> {
+        generate(self, request)
+    }
+}
+
+impl Maven {
     fn project_structure(
         project_path: &Path,
-        group: &str,
+        package: &str,
         name: &str,
-    ) -> (HashMap<PathBuf, &'static str>, Vec<PathBuf>) {
+    ) -> (HashMap<PathBuf, TemplateFile>, Vec<PathBuf>) {
+        let package_dir = package.replace('.', "/");
         let root = project_path.to_path_buf();
-        let main = project_path.join(format!("src/{MAIN}/{group}/{name}"));
-        let tests = project_path.join(format!("src/{TESTS}/{group}/{name}/example"));
+        let main = project_path.join(format!("src/{MAIN}/{package_dir}"));
+        let tests = project_path.join(format!("src/{TESTS}/{package_dir}/example"));
         let github = project_path.join(".github/workflows");
 
         let mut template_files = HashMap::new();
 
         // All the files in the root of the projects
-        template_files.insert(root.join("pom.xml"), "xml.pom");
-        template_files.insert(root.join("checkstyle.xml"), "xml.checkstyle");
-        template_files.insert(root.join("README.md"), "md.README");
-        template_files.insert(root.join("LICENSE.md"), "build.license");
+        template_files.insert(root.join("pom.xml"), "xml.pom".into());
+        template_files.insert(root.join("checkstyle.xml"), "xml.checkstyle".into());
+        template_files.insert(
+            root.join("README.md"),
+            TemplateFile::from("md.README").component("readme"),
+        );
+        template_files.insert(
+            root.join("LICENSE.md"),
+            TemplateFile::from("build.license").component("license"),
+        );
 
         // All files in the main directory
-        template_files.insert(main.join("Entry.java"), "java.entry");
+        template_files.insert(main.join("Entry.java"), "java.entry".into());
 
         // All files in the test directory
-        template_files.insert(tests.join("Example.java"), "java.example");
+        template_files.insert(tests.join("Example.java"), "java.example".into());
 
         // Continuous integration files
-        template_files.insert(github.join(format!("{name}.yml")), "ci.github");
+        template_files.insert(
+            github.join(format!("{name}.yml")),
+            TemplateFile::from("ci.github").component("ci"),
+        );
 
         (template_files, vec![root, main, tests, github])
     }
 }
 
-impl<'a> BuildTemplate for Maven<'a> {
+impl BuildTemplate for Maven {
     fn define(
         &self,
         project_path: &Path,
         project_name: &str,
         license: &str,
         github_branch: &str,
+        inputs: &BTreeMap<String, String>,
     ) -> (
-        HashMap<PathBuf, &'static str>,
+        HashMap<PathBuf, TemplateFile>,
         Vec<PathBuf>,
         HashMap<&'static str, Value>,
     ) {
         let mut context = HashMap::new();
+        let package = format!("{}.{}", inputs["group"], naming::java_segment(project_name));
 
-        context.insert("name", Value::from_serializable(&project_name));
-        context.insert("branch", Value::from_serializable(&github_branch));
-        context.insert("group", Value::from_serializable(&self.0));
-        context.insert("license_id", Value::from_serializable(&license));
+        context.insert("name", Value::from_serialize(project_name));
+        context.insert("branch", Value::from_serialize(github_branch));
+        context.insert("license_id", Value::from_serialize(license));
+        context.insert("package", Value::from_serialize(&package));
 
-        let (files, dirs) = Maven::project_structure(project_path, self.0, project_name);
+        let (files, dirs) = Maven::project_structure(project_path, &package, project_name);
 
         (files, dirs, context)
     }
 
-    fn get_templates() -> &'static [(&'static str, &'static str)] {
-        MAVEN_TEMPLATES
+    fn check_name(&self, project_name: &str, inputs: &BTreeMap<String, String>) -> Result<()> {
+        naming::check_group(&inputs["group"])?;
+        naming::check_artifact(self.name(), project_name)
+    }
+
+    fn project_path(&self, project_path: &Path, project_name: &str) -> PathBuf {
+        if let Some(parent) = project_path.parent() {
+            parent.join(project_name)
+        } else {
+            Path::new(project_name).to_path_buf()
+        }
     }
 }
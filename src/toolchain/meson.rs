@@ -1,17 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::Result;
 use minijinja::value::Value;
 use serde::{Deserialize, Serialize};
 
+use crate::naming;
 use crate::{
-    builtin_templates, compute_template, define_license, define_name, BuildTemplate, CreateProject,
+    builtin_templates, generate, BuildTemplate, BuiltinTemplate, Error, GenerationRequest,
+    InputKind, Mode, Plan, Result, TemplateFile, Toolchain, ToolchainInput,
 };
 
 const MESON_FILE: &str = "meson.build";
 
-static MESON_TEMPLATES: &[(&str, &str)] = &builtin_templates!["meson" =>
+static MESON_TEMPLATES: &[BuiltinTemplate] = &builtin_templates!["meson" =>
     ("build.root", "root.build"),
     ("build.cli", "cli.build"),
     ("build.lib", "lib.build"),
@@ -22,7 +24,7 @@ static MESON_TEMPLATES: &[(&str, &str)] = &builtin_templates!["meson" =>
     ("header", "header"),
     ("Dockerfile", "Dockerfile"),
     ("docker.compose", "docker-compose.yml"),
-    ("run.tests", "run_tests.sh"),
+    ("run.tests", "run_tests.sh", 0o755),
     ("md.README", "README.md"),
     ("ci.github", "github.yml")
 ];
@@ -31,43 +33,87 @@ static MESON_TEMPLATES: &[(&str, &str)] = &builtin_templates!["meson" =>
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProjectKind {
     /// C-language project
+    #[serde(rename = "c")]
     C,
     /// C++-language project
+    #[serde(rename = "c++")]
     Cxx,
 }
 
-/// A meson project data.
-pub struct Meson(ProjectKind);
+impl ProjectKind {
+    /// Returns the name of the kind as accepted on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectKind::C => "c",
+            ProjectKind::Cxx => "c++",
+        }
+    }
+}
 
-impl CreateProject for Meson {
-    fn create_project(
-        &self,
-        project_name: &str,
-        project_path: &Path,
-        license: &str,
-        github_branch: &str,
-    ) -> Result<()> {
-        let project_name = define_name(project_name, project_path)?;
-        let license = define_license(license)?;
-        let template = self.build(project_path, project_name, license.id(), github_branch);
-        compute_template(template, license, project_path)
+impl FromStr for ProjectKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "c" => Ok(ProjectKind::C),
+            "c++" => Ok(ProjectKind::Cxx),
+            _ => Err(Error::InvalidInput {
+                input: "kind".to_owned(),
+                value: s.to_owned(),
+                reason: "not a valid meson project kind".to_owned(),
+                suggestion: None,
+            }),
+        }
     }
 }
 
-impl Meson {
-    /// Creates a new `Meson` instance.
-    pub fn new(kind: ProjectKind) -> Self {
-        Self(kind)
+/// A meson project.
+#[derive(Debug, Default)]
+pub struct Meson;
+
+impl Toolchain for Meson {
+    fn name(&self) -> &str {
+        "meson"
+    }
+
+    fn description(&self) -> &str {
+        "Generate a new meson project"
+    }
+
+    fn modes(&self) -> &[Mode] {
+        &[Mode::Project]
     }
 
+    fn inputs(&self) -> Vec<ToolchainInput> {
+        vec![ToolchainInput {
+            name: "kind".to_owned(),
+            kind: InputKind::Choice,
+            description: "Kind of a new meson project".to_owned(),
+            default: Some(ProjectKind::C.as_str().to_owned()),
+            choices: [ProjectKind::C, ProjectKind::Cxx]
+                .map(|kind| kind.as_str().to_owned())
+                .to_vec(),
+            short: Some('k'),
+            ..Default::default()
+        }]
+    }
+
+    fn templates(&self) -> &'static [BuiltinTemplate] {
+        MESON_TEMPLATES
+    }
+
+    fn plan(&self, request: &GenerationRequest) -> Result<Plan> {
+        generate(self, request)
+    }
+}
+
+impl Meson {
     // Build a map Path <-> template
     fn project_structure(
         project_path: &Path,
-        name: &str,
+        c_name: &str,
         src_ext: &str,
-    ) -> (HashMap<PathBuf, &'static str>, Vec<PathBuf>) {
-        let name = &name.replace('-', "_");
-
+    ) -> (HashMap<PathBuf, TemplateFile>, Vec<PathBuf>) {
         let root = project_path.to_path_buf();
         let cli = project_path.join("cli");
         let lib = project_path.join("lib");
@@ -77,29 +123,53 @@ impl Meson {
         let mut template_files = HashMap::new();
 
         // All the files in the root of the projects
-        template_files.insert(root.join(MESON_FILE), "build.root");
-        template_files.insert(root.join("README.md"), "md.README");
+        template_files.insert(root.join(MESON_FILE), "build.root".into());
+        template_files.insert(
+            root.join("README.md"),
+            TemplateFile::from("md.README").component("readme"),
+        );
 
         // All the files in the `cli/` directory of the project
-        template_files.insert(cli.join(MESON_FILE), "build.cli");
-        template_files.insert(cli.join(name).with_extension(src_ext), "source.bin");
+        template_files.insert(cli.join(MESON_FILE), "build.cli".into());
+        template_files.insert(
+            cli.join(c_name).with_extension(src_ext),
+            "source.bin".into(),
+        );
 
         // All the files in the `lib/` directory of the project
-        template_files.insert(lib.join(MESON_FILE), "build.lib");
-        template_files.insert(lib.join(name).with_extension("h"), "header");
-        template_files.insert(lib.join(name).with_extension(src_ext), "source.lib");
+        template_files.insert(lib.join(MESON_FILE), "build.lib".into());
+        template_files.insert(lib.join(c_name).with_extension("h"), "header".into());
+        template_files.insert(
+            lib.join(c_name).with_extension(src_ext),
+            "source.lib".into(),
+        );
 
         // All the tests for the project, in `tests/`
-        template_files.insert(tests.join(MESON_FILE), "build.test");
-        template_files.insert(tests.join(name).with_extension(src_ext), "source.test");
+        template_files.insert(tests.join(MESON_FILE), "build.test".into());
+        template_files.insert(
+            tests.join(c_name).with_extension(src_ext),
+            "source.test".into(),
+        );
 
         // All docker files
-        template_files.insert(root.join("Dockerfile"), "Dockerfile");
-        template_files.insert(root.join("docker-compose.yml"), "docker.compose");
-        template_files.insert(root.join("run_tests.sh"), "run.tests");
+        template_files.insert(
+            root.join("Dockerfile"),
+            TemplateFile::from("Dockerfile").component("docker"),
+        );
+        template_files.insert(
+            root.join("docker-compose.yml"),
+            TemplateFile::from("docker.compose").component("docker"),
+        );
+        template_files.insert(
+            root.join("run_tests.sh"),
+            TemplateFile::from("run.tests").component("docker"),
+        );
 
         // Continuous Integration
-        template_files.insert(github.join(format!("{name}.yml")), "ci.github");
+        template_files.insert(
+            github.join(format!("{c_name}.yml")),
+            TemplateFile::from("ci.github").component("ci"),
+        );
 
         (template_files, vec![root, cli, lib, tests, github])
     }
@@ -112,29 +182,37 @@ impl BuildTemplate for Meson {
         project_name: &str,
         license: &str,
         github_branch: &str,
+        inputs: &BTreeMap<String, String>,
     ) -> (
-        HashMap<PathBuf, &'static str>,
+        HashMap<PathBuf, TemplateFile>,
         Vec<PathBuf>,
         HashMap<&'static str, Value>,
     ) {
         let mut context = HashMap::new();
-        let (ext, params) = match self.0 {
+        let c_name = naming::c_identifier(project_name);
+        let kind = inputs["kind"].parse().unwrap_or(ProjectKind::C);
+        let (ext, params) = match kind {
             ProjectKind::C => ("c", "c_std=c99"),
             ProjectKind::Cxx => ("cpp", "cpp_std=c++11"),
         };
 
-        context.insert("name", Value::from_serializable(&project_name));
-        context.insert("branch", Value::from_serializable(&github_branch));
-        context.insert("exe", Value::from_serializable(&ext));
-        context.insert("params", Value::from_serializable(&params));
-        context.insert("license_id", Value::from_serializable(&license));
+        context.insert("name", Value::from_serialize(project_name));
+        context.insert("branch", Value::from_serialize(github_branch));
+        context.insert("exe", Value::from_serialize(ext));
+        context.insert("params", Value::from_serialize(params));
+        context.insert("license_id", Value::from_serialize(license));
+        context.insert("c_name", Value::from_serialize(&c_name));
 
-        let (files, dirs) = Meson::project_structure(project_path, project_name, ext);
+        let (files, dirs) = Meson::project_structure(project_path, &c_name, ext);
 
         (files, dirs, context)
     }
 
-    fn get_templates() -> &'static [(&'static str, &'static str)] {
-        MESON_TEMPLATES
+    fn check_name(&self, project_name: &str, _inputs: &BTreeMap<String, String>) -> Result<()> {
+        naming::check_c(self.name(), project_name)
+    }
+
+    fn hooks() -> &'static [&'static str] {
+        &["meson setup build"]
     }
 }
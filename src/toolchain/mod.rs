@@ -4,15 +4,300 @@ pub mod meson;
 pub mod poetry;
 pub mod yarn;
 
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use minijinja::value::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::{ConflictPolicy, Error, Options, Plan, Result};
+
+/// A toolchain able to generate a project.
+pub trait Toolchain {
+    /// Name of the toolchain, used as command name and recorded in the manifest.
+    fn name(&self) -> &str;
+
+    /// Description of the toolchain, shown in the command line help.
+    fn description(&self) -> &str;
+
+    /// What the toolchain generates.
+    fn modes(&self) -> &[Mode];
+
+    /// Inputs of the toolchain, besides the name, license and branch shared
+    /// by every toolchain.
+    fn inputs(&self) -> Vec<ToolchainInput>;
+
+    /// Templates embedded in the binary, none for the toolchains whose
+    /// templates are files on disk.
+    fn templates(&self) -> &'static [BuiltinTemplate] {
+        &[]
+    }
+
+    /// Computes the directories and files of a generation without writing them.
+    fn plan(&self, request: &GenerationRequest) -> Result<Plan>;
+
+    /// Generates a project, aborting if any of its files already exists.
+    fn generate(&self, request: &GenerationRequest) -> Result<()> {
+        self.plan(request)?.write(ConflictPolicy::default())
+    }
+}
+
+/// What a toolchain generates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// A new project
+    Project,
+    /// The CI configuration of an existing project
+    Ci,
+}
+
+impl Mode {
+    /// Returns the name of the mode.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Project => "project",
+            Mode::Ci => "ci",
+        }
+    }
+}
+
+/// Type of a toolchain input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputKind {
+    /// Any string
+    #[default]
+    String,
+    /// `true` or `false`
+    Bool,
+    /// An integer number
+    Integer,
+    /// One of the `choices` of the input
+    Choice,
+}
+
+/// An input of a toolchain, available to its templates as a variable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolchainInput {
+    /// Name of the input.
+    pub name: String,
+    /// Type of the input.
+    pub kind: InputKind,
+    /// Description of the input, shown in the command line help.
+    pub description: String,
+    /// Default value, the input is required when missing.
+    pub default: Option<String>,
+    /// Admitted values of a `choice` input.
+    pub choices: Vec<String>,
+    /// Component using the input, the input is required only when the
+    /// component is generated.
+    pub component: Option<String>,
+    /// Whether the input is passed as a positional argument, before the
+    /// project path, instead of an option.
+    pub positional: bool,
+    /// Short form of the option, if any.
+    pub short: Option<char>,
+}
+
+impl ToolchainInput {
+    /// Checks a value of the input and converts it to its type.
+    pub fn parse(&self, value: &str) -> Result<Value> {
+        let invalid = |reason: String| Error::InvalidInput {
+            input: self.name.to_owned(),
+            value: value.to_owned(),
+            reason,
+            suggestion: None,
+        };
+        let value = match self.kind {
+            InputKind::String => Value::from(value),
+            InputKind::Bool => Value::from(
+                value
+                    .parse::<bool>()
+                    .map_err(|_| invalid("it must be true or false".to_owned()))?,
+            ),
+            InputKind::Integer => Value::from(
+                value
+                    .parse::<i64>()
+                    .map_err(|_| invalid("it must be an integer".to_owned()))?,
+            ),
+            InputKind::Choice => {
+                if !self.choices.iter().any(|choice| choice == value) {
+                    return Err(invalid(format!(
+                        "it must be one of {}",
+                        self.choices.join(", ")
+                    )));
+                }
+                Value::from(value)
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// The values a generation is run with.
+#[derive(Debug, Clone)]
+pub struct GenerationRequest {
+    /// Path to the project.
+    pub project_path: PathBuf,
+    /// Name of the project, the last component of the path when empty.
+    pub name: String,
+    /// SPDX expression of the licenses.
+    pub license: String,
+    /// Branch the CI workflows run on.
+    pub branch: String,
+    /// Author of the project, if any.
+    pub author: String,
+    /// Email of the author, if any.
+    pub email: String,
+    /// Holder of the copyright, the author when empty, or else the authors
+    /// of the project.
+    pub copyright_holder: String,
+    /// Year of the copyright notices, the current one when empty.
+    pub year: String,
+    /// Values of the toolchain inputs, the missing ones take their default value.
+    pub inputs: BTreeMap<String, String>,
+    /// Settings customizing the generation.
+    pub options: Options,
+}
+
+impl GenerationRequest {
+    /// Creates a request for the project in `project_path`, under the MIT
+    /// license and on the `main` branch.
+    pub fn new(project_path: impl Into<PathBuf>) -> Self {
+        Self {
+            project_path: project_path.into(),
+            name: String::new(),
+            license: "MIT".to_owned(),
+            branch: "main".to_owned(),
+            author: String::new(),
+            email: String::new(),
+            copyright_holder: String::new(),
+            year: String::new(),
+            inputs: BTreeMap::new(),
+            options: Options::default(),
+        }
+    }
+
+    /// Checks the values of the `inputs` of a toolchain, returning them as
+    /// recorded in the manifest and as template variables.
+    pub(crate) fn resolve_inputs(
+        &self,
+        toolchain: &str,
+        inputs: &[ToolchainInput],
+    ) -> Result<(BTreeMap<String, String>, HashMap<String, Value>)> {
+        let mut values = BTreeMap::new();
+        let mut context = HashMap::new();
+
+        for input in inputs {
+            let value = self
+                .inputs
+                .get(&input.name)
+                .cloned()
+                .or_else(|| input.default.clone());
+            let excluded = input
+                .component
+                .as_ref()
+                .is_some_and(|component| !self.options.includes(component));
+            let (value, variable) = match value {
+                Some(value) => {
+                    let variable = input.parse(&value)?;
+                    (value, variable)
+                }
+                // The files using the input are not generated
+                None if excluded => (String::new(), Value::from("")),
+                None => {
+                    return Err(Error::MissingInput {
+                        toolchain: toolchain.to_owned(),
+                        input: input.name.to_owned(),
+                    })
+                }
+            };
+            values.insert(input.name.to_owned(), value);
+            context.insert(input.name.to_owned(), variable);
+        }
+
+        Ok((values, context))
+    }
+}
+
+/// A template embedded in the binary.
+#[derive(Debug, Clone, Copy)]
+pub struct BuiltinTemplate {
+    /// Name used to refer to the template.
+    pub name: &'static str,
+    /// File name of the template, user templates with the same file name replace it.
+    pub file: &'static str,
+    /// Source of the template.
+    pub source: &'static str,
+    /// Attributes of the files rendered from the template.
+    pub attributes: FileAttributes,
+}
+
+/// Attributes applied to a generated file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileAttributes {
+    /// Unix permission bits, the default ones of the platform when missing.
+    pub mode: Option<u32>,
+}
+
+impl FileAttributes {
+    /// Attributes of an executable file.
+    pub const EXECUTABLE: Self = Self { mode: Some(0o755) };
+
+    /// Returns the attributes set in `self`, taking the missing ones from `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            mode: self.mode.or(other.mode),
+        }
+    }
+}
+
+/// A file of the project structure of a toolchain.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TemplateFile {
+    /// Name of the template the file is rendered from.
+    pub(crate) name: &'static str,
+    /// Attributes of the file, replacing the ones of the template.
+    pub(crate) attributes: FileAttributes,
+    /// Component the file belongs to, the file is always generated when missing.
+    pub(crate) component: Option<&'static str>,
+}
+
+impl TemplateFile {
+    /// Puts the file in a component.
+    pub(crate) fn component(self, component: &'static str) -> Self {
+        Self {
+            component: Some(component),
+            ..self
+        }
+    }
+}
+
+impl From<&'static str> for TemplateFile {
+    fn from(name: &'static str) -> Self {
+        Self {
+            name,
+            attributes: FileAttributes::default(),
+            component: None,
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! builtin_templates {
-    ($root:expr => $(($name:expr, $template:expr)),+) => {
+    (@mode) => { None };
+    (@mode $mode:expr) => { Some($mode) };
+    ($root:expr => $(($name:expr, $template:expr $(, $mode:expr)?)),+) => {
         [
         $(
-            (
-                $name,
-                include_str!(concat!(env!("CARGO_MANIFEST_DIR"),"/templates/", $root, "/", $template)),
-            )
+            $crate::BuiltinTemplate {
+                name: $name,
+                file: $template,
+                source: include_str!(concat!(env!("CARGO_MANIFEST_DIR"),"/templates/", $root, "/", $template)),
+                attributes: $crate::FileAttributes {
+                    mode: $crate::builtin_templates!(@mode $($mode)?),
+                },
+            }
         ),+
         ]
     }
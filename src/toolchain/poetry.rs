@@ -1,14 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
 use minijinja::value::Value;
 
+use crate::naming;
 use crate::{
-    builtin_templates, compute_template, define_license, define_name, BuildTemplate, CreateProject,
+    builtin_templates, generate, BuildTemplate, BuiltinTemplate, GenerationRequest, Mode, Plan,
+    Result, TemplateFile, Toolchain, ToolchainInput,
 };
 
-static POETRY_TEMPLATES: &[(&str, &str)] = &builtin_templates!["poetry" =>
+static POETRY_TEMPLATES: &[BuiltinTemplate] = &builtin_templates!["poetry" =>
     ("toml.pyproject", "pyproject.toml"),
     ("yaml.pre-commit", ".pre-commit-config.yaml"),
     ("md.README", "README.md"),
@@ -19,58 +20,77 @@ static POETRY_TEMPLATES: &[(&str, &str)] = &builtin_templates!["poetry" =>
 ];
 
 /// A poetry project data.
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct Poetry;
 
-impl CreateProject for Poetry {
-    fn create_project(
-        &self,
-        project_name: &str,
-        project_path: &Path,
-        license: &str,
-        github_branch: &str,
-    ) -> Result<()> {
-        let project_name = define_name(project_name, project_path)?;
-        let license = define_license(license)?;
-        let template = self.build(project_path, project_name, license.id(), github_branch);
-        compute_template(template, license, project_path)
+impl Toolchain for Poetry {
+    fn name(&self) -> &str {
+        "poetry"
     }
-}
 
-impl Poetry {
-    /// Creates a new `Poetry` instance.
-    pub fn new() -> Self {
-        Self
+    fn description(&self) -> &str {
+        "Generate a new poetry project"
+    }
+
+    fn modes(&self) -> &[Mode] {
+        &[Mode::Project]
+    }
+
+    fn inputs(&self) -> Vec<ToolchainInput> {
+        Vec::new()
     }
 
+    fn templates(&self) -> &'static [BuiltinTemplate] {
+        POETRY_TEMPLATES
+    }
+
+    fn plan(&self, request: &GenerationRequest) -> Result<Plan> {
+        generate(self, request)
+    }
+}
+
+impl Poetry {
     fn project_structure(
         project_path: &Path,
         name: &str,
-    ) -> (HashMap<PathBuf, &'static str>, Vec<PathBuf>) {
+        module_name: &str,
+    ) -> (HashMap<PathBuf, TemplateFile>, Vec<PathBuf>) {
         let root = project_path.to_path_buf();
-        let main = project_path.join(name);
-        let data = project_path.join(format!("{name}/data"));
-        let tests = project_path.join(format!("{name}/tests"));
+        let main = project_path.join(module_name);
+        let data = project_path.join(format!("{module_name}/data"));
+        let tests = project_path.join(format!("{module_name}/tests"));
         let github = project_path.join(".github/workflows");
 
         let mut template_files = HashMap::new();
 
         // All the files in the root of the projects
-        template_files.insert(root.join("pyproject.toml"), "toml.pyproject");
-        template_files.insert(root.join(".pre-commit-config.yaml"), "yaml.pre-commit");
-        template_files.insert(root.join("README.md"), "md.README");
-        template_files.insert(root.join("LICENSE.md"), "build.license");
+        template_files.insert(root.join("pyproject.toml"), "toml.pyproject".into());
+        template_files.insert(
+            root.join(".pre-commit-config.yaml"),
+            "yaml.pre-commit".into(),
+        );
+        template_files.insert(
+            root.join("README.md"),
+            TemplateFile::from("md.README").component("readme"),
+        );
+        template_files.insert(
+            root.join("LICENSE.md"),
+            TemplateFile::from("build.license").component("license"),
+        );
 
         // All files in the main directory
-        template_files.insert(main.join("__init__.py"), "py.__init__");
-        template_files.insert(main.join("__main__.py"), "py.__main__");
+        template_files.insert(main.join("__init__.py"), "py.__init__".into());
+        template_files.insert(main.join("__main__.py"), "py.__main__".into());
 
         // All files in the tests/ directory
-        template_files.insert(tests.join("__init__.py"), "py.__init__");
-        template_files.insert(tests.join("test_sum.py"), "py.test");
+        template_files.insert(tests.join("__init__.py"), "py.__init__".into());
+        template_files.insert(tests.join("test_sum.py"), "py.test".into());
 
         // Continuous integration files
-        template_files.insert(github.join(format!("{name}.yml")), "ci.github");
+        template_files.insert(
+            github.join(format!("{name}.yml")),
+            TemplateFile::from("ci.github").component("ci"),
+        );
 
         (template_files, vec![root, main, data, tests, github])
     }
@@ -83,23 +103,34 @@ impl BuildTemplate for Poetry {
         project_name: &str,
         license: &str,
         github_branch: &str,
+        _inputs: &BTreeMap<String, String>,
     ) -> (
-        HashMap<PathBuf, &'static str>,
+        HashMap<PathBuf, TemplateFile>,
         Vec<PathBuf>,
         HashMap<&'static str, Value>,
     ) {
         let mut context = HashMap::new();
+        let module_name = naming::python_module(project_name);
 
-        context.insert("name", Value::from_serializable(&project_name));
-        context.insert("branch", Value::from_serializable(&github_branch));
-        context.insert("license_id", Value::from_serializable(&license));
+        context.insert("name", Value::from_serialize(project_name));
+        context.insert("branch", Value::from_serialize(github_branch));
+        context.insert("license_id", Value::from_serialize(license));
+        context.insert(
+            "package_name",
+            Value::from_serialize(naming::python_package(project_name)),
+        );
+        context.insert("module_name", Value::from_serialize(&module_name));
 
-        let (files, dirs) = Poetry::project_structure(project_path, project_name);
+        let (files, dirs) = Poetry::project_structure(project_path, project_name, &module_name);
 
         (files, dirs, context)
     }
 
-    fn get_templates() -> &'static [(&'static str, &'static str)] {
-        POETRY_TEMPLATES
+    fn check_name(&self, project_name: &str, _inputs: &BTreeMap<String, String>) -> Result<()> {
+        naming::check_python(self.name(), project_name)
+    }
+
+    fn hooks() -> &'static [&'static str] {
+        &["poetry lock"]
     }
 }
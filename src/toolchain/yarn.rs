@@ -1,59 +1,75 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
 use minijinja::value::Value;
 
+use crate::naming;
 use crate::{
-    builtin_templates, compute_template, define_license, define_name, BuildTemplate, CreateCi,
+    builtin_templates, generate, BuildTemplate, BuiltinTemplate, GenerationRequest, Mode, Plan,
+    Result, TemplateFile, Toolchain, ToolchainInput,
 };
 
-static YARN_TEMPLATES: &[(&str, &str)] = &builtin_templates!["yarn" =>
+static YARN_TEMPLATES: &[BuiltinTemplate] = &builtin_templates!["yarn" =>
     ("md.README", "README.md"),
     ("ci.gitlab", ".gitlab-ci.yml"),
     ("ci.github", "github.yml")
 ];
 
 /// A yarn project data.
-#[derive(Default)]
+#[derive(Debug, Default)]
 pub struct Yarn;
 
-impl CreateCi for Yarn {
-    fn create_ci(
-        &self,
-        project_name: &str,
-        project_path: &Path,
-        license: &str,
-        github_branch: &str,
-    ) -> Result<()> {
-        let project_name = define_name(project_name, project_path)?;
-        let license = define_license(license)?;
-        let template = Yarn.build(project_path, project_name, license.id(), github_branch);
-        compute_template(template, license, project_path)
+impl Toolchain for Yarn {
+    fn name(&self) -> &str {
+        "yarn"
     }
-}
 
-impl Yarn {
-    /// Creates a new `Yarn` instance.
-    pub fn new() -> Self {
-        Self
+    fn description(&self) -> &str {
+        "Generate a new yarn project"
     }
 
+    fn modes(&self) -> &[Mode] {
+        &[Mode::Ci]
+    }
+
+    fn inputs(&self) -> Vec<ToolchainInput> {
+        Vec::new()
+    }
+
+    fn templates(&self) -> &'static [BuiltinTemplate] {
+        YARN_TEMPLATES
+    }
+
+    fn plan(&self, request: &GenerationRequest) -> Result<Plan> {
+        generate(self, request)
+    }
+}
+
+impl Yarn {
     fn project_structure(
         project_path: &Path,
-        name: &str,
-    ) -> (HashMap<PathBuf, &'static str>, Vec<PathBuf>) {
+        file_stem: &str,
+    ) -> (HashMap<PathBuf, TemplateFile>, Vec<PathBuf>) {
         let root = project_path.to_path_buf();
         let github = project_path.join(".github/workflows");
 
         let mut template_files = HashMap::new();
 
         // README
-        template_files.insert(root.join("README.md"), "md.README");
+        template_files.insert(
+            root.join("README.md"),
+            TemplateFile::from("md.README").component("readme"),
+        );
 
         // Continuous Integration
-        template_files.insert(root.join(".gitlab-ci.yml"), "ci.gitlab");
-        template_files.insert(github.join(format!("{name}.yml")), "ci.github");
+        template_files.insert(
+            root.join(".gitlab-ci.yml"),
+            TemplateFile::from("ci.gitlab").component("ci"),
+        );
+        template_files.insert(
+            github.join(format!("{file_stem}.yml")),
+            TemplateFile::from("ci.github").component("ci"),
+        );
 
         (template_files, vec![root, github])
     }
@@ -66,23 +82,26 @@ impl BuildTemplate for Yarn {
         project_name: &str,
         license: &str,
         github_branch: &str,
+        _inputs: &BTreeMap<String, String>,
     ) -> (
-        HashMap<PathBuf, &'static str>,
+        HashMap<PathBuf, TemplateFile>,
         Vec<PathBuf>,
         HashMap<&'static str, Value>,
     ) {
         let mut context = HashMap::new();
+        let file_stem = naming::npm_file_stem(project_name);
 
-        context.insert("name", Value::from_serializable(&project_name));
-        context.insert("branch", Value::from_serializable(&github_branch));
-        context.insert("license_id", Value::from_serializable(&license));
+        context.insert("name", Value::from_serialize(project_name));
+        context.insert("branch", Value::from_serialize(github_branch));
+        context.insert("license_id", Value::from_serialize(license));
+        context.insert("file_stem", Value::from_serialize(&file_stem));
 
-        let (files, dirs) = Yarn::project_structure(project_path, project_name);
+        let (files, dirs) = Yarn::project_structure(project_path, &file_stem);
 
         (files, dirs, context)
     }
 
-    fn get_templates() -> &'static [(&'static str, &'static str)] {
-        YARN_TEMPLATES
+    fn check_name(&self, project_name: &str, _inputs: &BTreeMap<String, String>) -> Result<()> {
+        naming::check_npm(self.name(), project_name)
     }
 }
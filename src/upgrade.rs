@@ -0,0 +1,359 @@
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use tracing::info;
+
+use crate::manifest::relative_path;
+use crate::reuse::{DEP5_FILE, FORMAT_INPUT};
+use crate::{
+    hash, FileAttributes, GenerationRequest, Manifest, Options, Plan, PlannedFile, Registry,
+    Result, ReuseFormat, MANIFEST_FILE,
+};
+
+/// How to report the changes which cannot be merged into an edited file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStyle {
+    /// Write the conflicting hunks inside the file, between conflict markers
+    #[default]
+    Markers,
+    /// Leave the file untouched and write the new changes to a `*.rej` file
+    Reject,
+}
+
+/// The outcome of an upgrade.
+#[derive(Debug, Clone, Default)]
+pub struct Upgrade {
+    /// Files to be written to apply the upgrade.
+    pub plan: Plan,
+    /// Pristine files replaced with their new version.
+    pub updated: Vec<PathBuf>,
+    /// Edited files the new changes have been merged into.
+    pub merged: Vec<PathBuf>,
+    /// Edited files whose changes conflict with the new ones.
+    pub conflicts: Vec<PathBuf>,
+    /// Generated files removed by the user, which are not restored.
+    pub removed: Vec<PathBuf>,
+    /// Edited files left untouched, since the version they were generated
+    /// from cannot be rendered again to merge the new changes.
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Renders again the templates of a project, using the inputs and the
+/// variables recorded in its manifest.
+pub(crate) fn replay(manifest: &Manifest, project_path: &Path, options: &Options) -> Result<Plan> {
+    let mut inputs = manifest.inputs.clone();
+    let mut common = |key: &str| inputs.remove(key).unwrap_or_default();
+    let name = common("name");
+    let license = common("license");
+    let branch = common("branch");
+    let author = common("author");
+    let email = common("email");
+    let copyright_holder = common("copyright_holder");
+    let year = common("year");
+    // The projects generated before REUSE.toml have a dep5 file
+    let reuse_format = match common(FORMAT_INPUT).as_str() {
+        "" if manifest.files.contains_key(DEP5_FILE) => ReuseFormat::Dep5,
+        "" => ReuseFormat::Toml,
+        format => format.parse()?,
+    };
+
+    // The variables passed now take priority over the recorded ones
+    let mut vars = manifest.vars.clone();
+    vars.extend(options.vars.clone());
+    // The recorded components are generated again, as long as they exist
+    let (only, without) = if manifest.components.is_empty() {
+        (Vec::new(), Vec::new())
+    } else {
+        (manifest.components.clone(), Vec::new())
+    };
+
+    let request = GenerationRequest {
+        project_path: project_path.to_path_buf(),
+        name,
+        license,
+        branch,
+        author,
+        email,
+        copyright_holder,
+        year,
+        inputs,
+        options: Options {
+            vars,
+            only,
+            without,
+            reuse_format,
+            ..options.clone()
+        },
+    };
+
+    Registry::with_packs(&options.packs_dirs)?
+        .find(&manifest.toolchain)?
+        .plan(&request)
+}
+
+/// Computes the upgrade of a project to the current templates.
+///
+/// The user templates in `options` replace the built-in ones, as in a
+/// regular generation.
+///
+/// The templates recorded in the project manifest and the current ones
+/// are both rendered and their differences are merged into the files on
+/// disk: pristine files are replaced, edited files are merged three-way.
+/// A manifest recording no templates provides no common base: the files
+/// edited since the generation are skipped rather than merged.
+pub fn upgrade(project_path: &Path, style: MergeStyle, options: &Options) -> Result<Upgrade> {
+    let manifest = Manifest::read(project_path)?;
+
+    let old = if manifest.templates.is_empty() {
+        None
+    } else {
+        // Renders the template sources recorded by the previous generation
+        let options = Options {
+            template_sources: manifest.templates.clone(),
+            ..options.clone()
+        };
+        Some(replay(&manifest, project_path, &options)?)
+    };
+    let new = replay(&manifest, project_path, options)?;
+
+    let mut upgrade = Upgrade {
+        plan: Plan {
+            root: new.root.clone(),
+            dirs: new.dirs.clone(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    for file in new.files {
+        let relative = relative_path(&file.path, project_path);
+        if relative == MANIFEST_FILE {
+            upgrade.plan.files.push(file);
+            continue;
+        }
+
+        let base = old
+            .as_ref()
+            .and_then(|old| old.files.iter().find(|old| old.path == file.path))
+            .map(|old| old.content.as_str());
+
+        let Ok(current) = read_to_string(&file.path) else {
+            if manifest.files.contains_key(&relative) {
+                info!("{} has been removed, not restoring it", file.path.display());
+                upgrade.removed.push(file.path);
+            } else {
+                upgrade.updated.push(file.path.clone());
+                upgrade.plan.files.push(file);
+            }
+            continue;
+        };
+
+        // Nothing changed either on disk or in the template
+        if current == file.content || base == Some(file.content.as_str()) {
+            continue;
+        }
+
+        let pristine = manifest.files.get(&relative) == Some(&hash(current.as_bytes()))
+            || base == Some(current.as_str());
+        if pristine {
+            upgrade.updated.push(file.path.clone());
+            upgrade.plan.files.push(file);
+            continue;
+        }
+
+        let Some(base) = base else {
+            upgrade.skipped.push(file.path);
+            continue;
+        };
+        match diffy::merge(base, &current, &file.content) {
+            Ok(merged) => {
+                upgrade.merged.push(file.path.clone());
+                upgrade.plan.files.push(PlannedFile {
+                    content: merged,
+                    ..file
+                });
+            }
+            Err(conflicted) => {
+                upgrade.conflicts.push(file.path.clone());
+                match style {
+                    MergeStyle::Markers => upgrade.plan.files.push(PlannedFile {
+                        content: conflicted,
+                        ..file
+                    }),
+                    MergeStyle::Reject => {
+                        let mut name = file.path.file_name().unwrap_or_default().to_os_string();
+                        name.push(".rej");
+                        let patch = diffy::create_patch(base, &file.content);
+                        upgrade.plan.files.push(PlannedFile {
+                            path: file.path.with_file_name(name),
+                            template: file.template,
+                            content: patch.to_string(),
+                            attributes: FileAttributes::default(),
+                            component: file.component,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    upgrade.plan.files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(upgrade)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs::{remove_file, write};
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::ConflictPolicy;
+
+    const README: &str = include_str!("../templates/poetry/README.md");
+
+    // The README template of an older version of sifis-generate
+    fn older_readme() -> BTreeMap<String, String> {
+        let readme = README.replace("{{ name }}'s description", "{{ name }}'s old description");
+        [("md.README".to_owned(), readme)].into()
+    }
+
+    fn generate_project(recorded: Option<&BTreeMap<String, String>>) -> (TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let project_path = dir.path().join("project");
+        let mut request = GenerationRequest::new(&project_path);
+        request.options.template_sources = recorded.cloned().unwrap_or_default();
+        Registry::builtin()
+            .find("poetry")
+            .unwrap()
+            .plan(&request)
+            .unwrap()
+            .write(ConflictPolicy::Abort)
+            .unwrap();
+        (dir, project_path)
+    }
+
+    // Replaces `from` with `to` in a file of the project
+    fn edit(path: &Path, from: &str, to: &str) {
+        let content = read_to_string(path).unwrap();
+        write(path, content.replace(from, to)).unwrap();
+    }
+
+    fn planned<'a>(upgrade: &'a Upgrade, path: &Path) -> Option<&'a str> {
+        upgrade
+            .plan
+            .files
+            .iter()
+            .find(|file| file.path == path)
+            .map(|file| file.content.as_str())
+    }
+
+    #[test]
+    fn pristine_file_is_replaced() {
+        let (_dir, project_path) = generate_project(Some(&older_readme()));
+        let readme = project_path.join("README.md");
+
+        let upgrade = upgrade(&project_path, MergeStyle::Markers, &Options::default()).unwrap();
+
+        assert_eq!(upgrade.updated, [readme.as_path()]);
+        let content = planned(&upgrade, &readme).unwrap();
+        assert!(content.contains("project's description"));
+    }
+
+    #[test]
+    fn unchanged_templates_leave_edited_files_alone() {
+        let (_dir, project_path) = generate_project(None);
+        edit(
+            &project_path.join("README.md"),
+            "Acknowledgements",
+            "Thanks",
+        );
+
+        let upgrade = upgrade(&project_path, MergeStyle::Markers, &Options::default()).unwrap();
+
+        assert!(upgrade.updated.is_empty());
+        assert!(upgrade.merged.is_empty());
+        assert!(upgrade.conflicts.is_empty());
+        assert!(upgrade.skipped.is_empty());
+    }
+
+    #[test]
+    fn changed_builtin_template_is_merged() {
+        let (_dir, project_path) = generate_project(Some(&older_readme()));
+        let readme = project_path.join("README.md");
+        edit(&readme, "Acknowledgements", "Thanks");
+
+        let upgrade = upgrade(&project_path, MergeStyle::Markers, &Options::default()).unwrap();
+
+        assert_eq!(upgrade.merged, [readme.as_path()]);
+        let content = planned(&upgrade, &readme).unwrap();
+        assert!(content.contains("# Thanks"));
+        assert!(content.contains("project's description"));
+    }
+
+    #[test]
+    fn conflicts_are_written_between_markers() {
+        let (_dir, project_path) = generate_project(Some(&older_readme()));
+        let readme = project_path.join("README.md");
+        edit(&readme, "project's old description", "mine");
+
+        let upgrade = upgrade(&project_path, MergeStyle::Markers, &Options::default()).unwrap();
+
+        assert_eq!(upgrade.conflicts, [readme.as_path()]);
+        let content = planned(&upgrade, &readme).unwrap();
+        assert!(content.contains("<<<<<<<"));
+        assert!(content.contains("mine"));
+        assert!(content.contains("project's description"));
+    }
+
+    #[test]
+    fn conflicts_are_written_to_reject_files() {
+        let (_dir, project_path) = generate_project(Some(&older_readme()));
+        let readme = project_path.join("README.md");
+        edit(&readme, "project's old description", "mine");
+
+        let upgrade = upgrade(&project_path, MergeStyle::Reject, &Options::default()).unwrap();
+
+        assert_eq!(upgrade.conflicts, [readme.as_path()]);
+        assert_eq!(planned(&upgrade, &readme), None);
+        let patch = planned(&upgrade, &project_path.join("README.md.rej")).unwrap();
+        assert!(patch.contains("-project's old description"));
+        assert!(patch.contains("+project's description"));
+    }
+
+    #[test]
+    fn removed_file_is_not_restored() {
+        let (_dir, project_path) = generate_project(Some(&older_readme()));
+        let readme = project_path.join("README.md");
+        remove_file(&readme).unwrap();
+
+        let upgrade = upgrade(&project_path, MergeStyle::Markers, &Options::default()).unwrap();
+
+        assert_eq!(upgrade.removed, [readme.as_path()]);
+        assert_eq!(planned(&upgrade, &readme), None);
+    }
+
+    #[test]
+    fn edited_file_without_base_is_skipped() {
+        let (_dir, project_path) = generate_project(Some(&older_readme()));
+        let readme = project_path.join("README.md");
+        edit(&readme, "Acknowledgements", "Thanks");
+
+        // The manifest records no templates to render the previous version
+        let mut manifest = Manifest::read(&project_path).unwrap();
+        manifest.templates.clear();
+        write(
+            project_path.join(MANIFEST_FILE),
+            manifest.to_toml().unwrap(),
+        )
+        .unwrap();
+
+        let upgrade = upgrade(&project_path, MergeStyle::Markers, &Options::default()).unwrap();
+
+        assert_eq!(upgrade.skipped, [readme.as_path()]);
+        assert!(upgrade.conflicts.is_empty());
+        assert_eq!(planned(&upgrade, &readme), None);
+    }
+}
@@ -1,4 +1,4 @@
-package {{ group }}.{{ name }};
+package {{ package }};
 
 public class Entry {
     public static void main(String[] args) {
@@ -1,4 +1,4 @@
-package {{ group }}.{{ name }}.example;
+package {{ package }}.example;
 
 import org.junit.Test;
 
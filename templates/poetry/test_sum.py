@@ -1,7 +1,3 @@
-{{ name | comment_license("#") }}
-{% for line in license.text %}
-{{ line | comment_license("#") }}
-{% endfor %}
 
 def test_sum():
     """Simple test."""